@@ -2,6 +2,19 @@
 
 pub mod prelude;
 
+pub mod sea_orm_active_enums;
+
+pub mod t_audit_log;
+pub mod t_list_invitations;
+pub mod t_list_members;
+pub mod t_lists;
+pub mod t_permissions;
 pub mod t_refresh_token;
+pub mod t_role_permissions;
+pub mod t_roles;
+pub mod t_tags;
+pub mod t_todo_items;
+pub mod t_todo_tags;
 pub mod t_todos;
+pub mod t_user_roles;
 pub mod t_users;
@@ -1,5 +1,16 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
+pub use super::t_audit_log::Entity as TAuditLog;
+pub use super::t_list_invitations::Entity as TListInvitations;
+pub use super::t_list_members::Entity as TListMembers;
+pub use super::t_lists::Entity as TLists;
+pub use super::t_permissions::Entity as TPermissions;
 pub use super::t_refresh_token::Entity as TRefreshToken;
+pub use super::t_role_permissions::Entity as TRolePermissions;
+pub use super::t_roles::Entity as TRoles;
+pub use super::t_tags::Entity as TTags;
+pub use super::t_todo_items::Entity as TTodoItems;
+pub use super::t_todo_tags::Entity as TTodoTags;
 pub use super::t_todos::Entity as TTodos;
+pub use super::t_user_roles::Entity as TUserRoles;
 pub use super::t_users::Entity as TUsers;
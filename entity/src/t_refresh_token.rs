@@ -7,11 +7,12 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    #[sea_orm(column_type = "JsonBinary", nullable)]
-    pub data: Option<Json>,
     pub expired_at: DateTimeWithTimeZone,
     pub user_id: Uuid,
     pub token: Uuid,
+    pub family_id: Uuid,
+    pub parent_id: Option<i32>,
+    pub revoked_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
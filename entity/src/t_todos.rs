@@ -1,6 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
-use chrono::serde::ts_milliseconds;
+use super::sea_orm_active_enums::TodoPriority;
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option};
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -33,10 +34,73 @@ pub struct Model {
 
     #[serde(rename = "userId")]
     pub user_id: Uuid,
+
+    #[serde(
+        rename = "deletedAt",
+        default,
+        with = "ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    pub version: i32,
+
+    #[serde(
+        rename = "dueAt",
+        default,
+        with = "ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub due_at: Option<DateTime<Utc>>,
+
+    pub priority: TodoPriority,
+
+    #[serde(
+        rename = "remindAt",
+        default,
+        with = "ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remind_at: Option<DateTime<Utc>>,
+
+    #[serde(
+        rename = "reminderSentAt",
+        default,
+        with = "ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reminder_sent_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+
+    #[serde(rename = "seriesId", default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<Uuid>,
+
+    // Due date of the first todo of the series, None on the first one
+    #[serde(
+        rename = "seriesStartAt",
+        default,
+        with = "ts_milliseconds_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub series_start_at: Option<DateTime<Utc>>,
+
+    // Shared list the todo belongs to, None for personal todos
+    #[serde(rename = "listId", default, skip_serializing_if = "Option::is_none")]
+    pub list_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::t_lists::Entity",
+        from = "Column::ListId",
+        to = "super::t_lists::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    TLists,
     #[sea_orm(
         belongs_to = "super::t_users::Entity",
         from = "Column::UserId",
@@ -45,6 +109,16 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     TUsers,
+    #[sea_orm(has_many = "super::t_todo_tags::Entity")]
+    TTodoTags,
+    #[sea_orm(has_many = "super::t_todo_items::Entity")]
+    TTodoItems,
+}
+
+impl Related<super::t_lists::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TLists.def()
+    }
 }
 
 impl Related<super::t_users::Entity> for Entity {
@@ -53,4 +127,25 @@ impl Related<super::t_users::Entity> for Entity {
     }
 }
 
+impl Related<super::t_todo_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TTodoTags.def()
+    }
+}
+
+impl Related<super::t_todo_items::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TTodoItems.def()
+    }
+}
+
+impl Related<super::t_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::t_todo_tags::Relation::TTags.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::t_todo_tags::Relation::TTodos.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
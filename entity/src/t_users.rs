@@ -15,12 +15,48 @@ pub struct Model {
     pub password: String,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
+    pub email_verified_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::t_audit_log::Entity")]
+    TAuditLog,
+    #[sea_orm(has_many = "super::t_list_invitations::Entity")]
+    TListInvitations,
+    #[sea_orm(has_many = "super::t_list_members::Entity")]
+    TListMembers,
+    #[sea_orm(has_many = "super::t_tags::Entity")]
+    TTags,
     #[sea_orm(has_many = "super::t_todos::Entity")]
     TTodos,
+    #[sea_orm(has_many = "super::t_user_roles::Entity")]
+    TUserRoles,
+}
+
+impl Related<super::t_audit_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TAuditLog.def()
+    }
+}
+
+impl Related<super::t_list_invitations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TListInvitations.def()
+    }
+}
+
+impl Related<super::t_list_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TListMembers.def()
+    }
+}
+
+impl Related<super::t_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TTags.def()
+    }
 }
 
 impl Related<super::t_todos::Entity> for Entity {
@@ -29,4 +65,19 @@ impl Related<super::t_todos::Entity> for Entity {
     }
 }
 
+impl Related<super::t_user_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TUserRoles.def()
+    }
+}
+
+impl Related<super::t_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::t_user_roles::Relation::TRoles.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::t_user_roles::Relation::TUsers.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
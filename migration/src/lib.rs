@@ -2,7 +2,22 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20250731_042456_create_user_table;
 mod m20250805_021726_create_todo_table;
-
+mod m20251017_081500_refresh_token_rotation;
+mod m20251017_093000_drop_refresh_token_data;
+mod m20251018_020000_create_role_tables;
+mod m20251018_050000_add_todo_search_vector;
+mod m20251018_083000_add_todo_deleted_at;
+mod m20251018_110000_add_todo_version;
+mod m20251018_140000_add_todo_schedule;
+mod m20251018_170000_add_todo_recurrence;
+mod m20251019_020000_create_tag_tables;
+mod m20251019_050000_create_todo_items;
+mod m20251019_080000_create_list_tables;
+mod m20251019_110000_create_audit_log;
+mod m20251019_140000_add_user_email_verified_at;
+mod m20251020_020000_create_permission_tables;
+mod m20251020_050000_add_todo_series_start;
+mod m20251020_080000_add_user_email_lower_index;
 
 pub struct Migrator;
 
@@ -12,6 +27,22 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20250731_042456_create_user_table::Migration),
             Box::new(m20250805_021726_create_todo_table::Migration),
+            Box::new(m20251017_081500_refresh_token_rotation::Migration),
+            Box::new(m20251017_093000_drop_refresh_token_data::Migration),
+            Box::new(m20251018_020000_create_role_tables::Migration),
+            Box::new(m20251018_050000_add_todo_search_vector::Migration),
+            Box::new(m20251018_083000_add_todo_deleted_at::Migration),
+            Box::new(m20251018_110000_add_todo_version::Migration),
+            Box::new(m20251018_140000_add_todo_schedule::Migration),
+            Box::new(m20251018_170000_add_todo_recurrence::Migration),
+            Box::new(m20251019_020000_create_tag_tables::Migration),
+            Box::new(m20251019_050000_create_todo_items::Migration),
+            Box::new(m20251019_080000_create_list_tables::Migration),
+            Box::new(m20251019_110000_create_audit_log::Migration),
+            Box::new(m20251019_140000_add_user_email_verified_at::Migration),
+            Box::new(m20251020_020000_create_permission_tables::Migration),
+            Box::new(m20251020_050000_add_todo_series_start::Migration),
+            Box::new(m20251020_080000_add_user_email_lower_index::Migration),
         ]
     }
 }
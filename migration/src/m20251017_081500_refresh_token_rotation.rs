@@ -0,0 +1,116 @@
+use crate::m20250731_042456_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // t_refresh_token was created outside of migrations, create it if missing
+        manager
+            .create_table(
+                Table::create()
+                    .table(RefreshToken::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RefreshToken::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RefreshToken::Data).json_binary().null())
+                    .col(
+                        ColumnDef::new(RefreshToken::ExpiredAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RefreshToken::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(RefreshToken::Token)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RefreshToken::Table, RefreshToken::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A family groups every token rotated from the same sign-in
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RefreshToken::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(RefreshToken::FamilyId)
+                            .uuid()
+                            .not_null()
+                            .extra("DEFAULT gen_random_uuid()".to_owned()),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(RefreshToken::ParentId).integer().null(),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(RefreshToken::RevokedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_refresh_token_family_id")
+                    .table(RefreshToken::Table)
+                    .col(RefreshToken::FamilyId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_t_refresh_token_family_id")
+                    .table(RefreshToken::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RefreshToken::Table)
+                    .drop_column(RefreshToken::FamilyId)
+                    .drop_column(RefreshToken::ParentId)
+                    .drop_column(RefreshToken::RevokedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum RefreshToken {
+    #[sea_orm(iden = "t_refresh_token")]
+    Table,
+    Id,
+    Data,
+    ExpiredAt,
+    UserId,
+    Token,
+    FamilyId,
+    ParentId,
+    RevokedAt,
+}
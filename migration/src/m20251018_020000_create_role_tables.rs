@@ -0,0 +1,97 @@
+use crate::m20250731_042456_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Role::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Role::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Role::Name).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(Role::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRole::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserRole::UserId).uuid().not_null())
+                    .col(ColumnDef::new(UserRole::RoleId).integer().not_null())
+                    .primary_key(Index::create().col(UserRole::UserId).col(UserRole::RoleId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserRole::Table, UserRole::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(UserRole::Table, UserRole::RoleId)
+                            .to(Role::Table, Role::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the built-in roles
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Role::Table)
+                    .columns([Role::Name])
+                    .values_panic(["admin".into()])
+                    .values_panic(["user".into()])
+                    .on_conflict(OnConflict::column(Role::Name).do_nothing().to_owned())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserRole::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Role::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Role {
+    #[sea_orm(iden = "t_roles")]
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum UserRole {
+    #[sea_orm(iden = "t_user_roles")]
+    Table,
+    UserId,
+    RoleId,
+}
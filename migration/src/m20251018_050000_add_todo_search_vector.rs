@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Title matches rank above description matches. The 'english' config must
+        // match the one TodoRepository passes to websearch_to_tsquery.
+        db.execute_unprepared(
+            r#"ALTER TABLE "t_todos" ADD COLUMN IF NOT EXISTS "search_vector" tsvector
+                GENERATED ALWAYS AS (
+                    setweight(to_tsvector('english', coalesce("title", '')), 'A') ||
+                    setweight(to_tsvector('english', coalesce("description", '')), 'B')
+                ) STORED"#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"CREATE INDEX IF NOT EXISTS "idx_t_todos_search_vector"
+                ON "t_todos" USING GIN ("search_vector")"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(r#"DROP INDEX IF EXISTS "idx_t_todos_search_vector""#)
+            .await?;
+
+        db.execute_unprepared(r#"ALTER TABLE "t_todos" DROP COLUMN IF EXISTS "search_vector""#)
+            .await?;
+
+        Ok(())
+    }
+}
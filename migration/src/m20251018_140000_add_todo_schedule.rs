@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // priority: 1 = low, 2 = medium, 3 = high, 4 = urgent
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todo::DueAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todo::Priority)
+                            .small_integer()
+                            .not_null()
+                            .default(2),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todo::RemindAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todo::ReminderSentAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_todos_due_at")
+                    .table(Todo::Table)
+                    .col(Todo::DueAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Used by the reminder job to find pending reminders
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX IF NOT EXISTS "idx_t_todos_pending_reminders"
+                   ON "t_todos" ("remind_at")
+                   WHERE "reminder_sent_at" IS NULL AND "deleted_at" IS NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for index in ["idx_t_todos_pending_reminders", "idx_t_todos_due_at"] {
+            manager
+                .drop_index(Index::drop().name(index).table(Todo::Table).to_owned())
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .drop_column(Todo::DueAt)
+                    .drop_column(Todo::Priority)
+                    .drop_column(Todo::RemindAt)
+                    .drop_column(Todo::ReminderSentAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    DueAt,
+    Priority,
+    RemindAt,
+    ReminderSentAt,
+}
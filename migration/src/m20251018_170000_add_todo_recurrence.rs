@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // series_id points to the first todo of a recurring series, NULL on the first one
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Todo::Recurrence).text().null())
+                    .add_column_if_not_exists(ColumnDef::new(Todo::SeriesId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // An occurrence is only ever generated once, even by concurrent jobs
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_todos_series_id_due_at")
+                    .table(Todo::Table)
+                    .col(Todo::SeriesId)
+                    .col(Todo::DueAt)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_t_todos_series_id_due_at")
+                    .table(Todo::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .drop_column(Todo::Recurrence)
+                    .drop_column(Todo::SeriesId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    Recurrence,
+    SeriesId,
+    DueAt,
+}
@@ -0,0 +1,123 @@
+use crate::m20250731_042456_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Tag::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Tag::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Tag::Name).string().not_null())
+                    .col(ColumnDef::new(Tag::Color).string().null())
+                    .col(
+                        ColumnDef::new(Tag::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(Tag::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Tag::Table, Tag::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tag names are unique per user, ignoring case
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE UNIQUE INDEX IF NOT EXISTS "idx_t_tags_user_id_name"
+                   ON "t_tags" ("user_id", LOWER("name"))"#,
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TodoTag::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TodoTag::TodoId).uuid().not_null())
+                    .col(ColumnDef::new(TodoTag::TagId).uuid().not_null())
+                    .primary_key(Index::create().col(TodoTag::TodoId).col(TodoTag::TagId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(TodoTag::Table, TodoTag::TodoId)
+                            .to(Todo::Table, Todo::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(TodoTag::Table, TodoTag::TagId)
+                            .to(Tag::Table, Tag::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The primary key covers lookups by todo, this one the tag filter
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_todo_tags_tag_id")
+                    .table(TodoTag::Table)
+                    .col(TodoTag::TagId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TodoTag::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tag::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tag {
+    #[sea_orm(iden = "t_tags")]
+    Table,
+    Id,
+    UserId,
+    Name,
+    Color,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TodoTag {
+    #[sea_orm(iden = "t_todo_tags")]
+    Table,
+    TodoId,
+    TagId,
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    Id,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TodoItem::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TodoItem::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(TodoItem::TodoId).uuid().not_null())
+                    .col(ColumnDef::new(TodoItem::Title).string().not_null())
+                    .col(
+                        ColumnDef::new(TodoItem::Completed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(TodoItem::Position).integer().not_null())
+                    .col(
+                        ColumnDef::new(TodoItem::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(TodoItem::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(TodoItem::Table, TodoItem::TodoId)
+                            .to(Todo::Table, Todo::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Items are always read per todo in position order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_todo_items_todo_id_position")
+                    .table(TodoItem::Table)
+                    .col(TodoItem::TodoId)
+                    .col(TodoItem::Position)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TodoItem::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TodoItem {
+    #[sea_orm(iden = "t_todo_items")]
+    Table,
+    Id,
+    TodoId,
+    Title,
+    Completed,
+    Position,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    Id,
+}
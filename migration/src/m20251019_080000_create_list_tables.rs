@@ -0,0 +1,241 @@
+use crate::m20250731_042456_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(List::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(List::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(List::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(List::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .col(
+                        ColumnDef::new(List::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // role: 1 = viewer, 2 = editor, 3 = owner
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListMember::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ListMember::ListId).uuid().not_null())
+                    .col(ColumnDef::new(ListMember::UserId).uuid().not_null())
+                    .col(ColumnDef::new(ListMember::Role).small_integer().not_null())
+                    .col(
+                        ColumnDef::new(ListMember::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ListMember::ListId)
+                            .col(ListMember::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ListMember::Table, ListMember::ListId)
+                            .to(List::Table, List::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ListMember::Table, ListMember::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every todo query looks up the lists of the current user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_list_members_user_id")
+                    .table(ListMember::Table)
+                    .col(ListMember::UserId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListInvitation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ListInvitation::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ListInvitation::ListId).uuid().not_null())
+                    .col(ColumnDef::new(ListInvitation::Email).string().not_null())
+                    .col(
+                        ColumnDef::new(ListInvitation::Role)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ListInvitation::InvitedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(ListInvitation::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ListInvitation::Table, ListInvitation::ListId)
+                            .to(List::Table, List::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ListInvitation::Table, ListInvitation::InvitedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One pending invitation per email and list, emails are matched ignoring case
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE UNIQUE INDEX IF NOT EXISTS "idx_t_list_invitations_list_id_email"
+                   ON "t_list_invitations" ("list_id", LOWER("email"))"#,
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX IF NOT EXISTS "idx_t_list_invitations_email"
+                   ON "t_list_invitations" (LOWER("email"))"#,
+            )
+            .await?;
+
+        // Deleting a list gives its todos back to the users who created them
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Todo::ListId).uuid().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_t_todos_list_id")
+                            .from_tbl(Todo::Table)
+                            .from_col(Todo::ListId)
+                            .to_tbl(List::Table)
+                            .to_col(List::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_todos_list_id")
+                    .table(Todo::Table)
+                    .col(Todo::ListId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_t_todos_list_id")
+                    .table(Todo::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .drop_foreign_key(Alias::new("fk_t_todos_list_id"))
+                    .drop_column(Todo::ListId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ListInvitation::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ListMember::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(List::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum List {
+    #[sea_orm(iden = "t_lists")]
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ListMember {
+    #[sea_orm(iden = "t_list_members")]
+    Table,
+    ListId,
+    UserId,
+    Role,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ListInvitation {
+    #[sea_orm(iden = "t_list_invitations")]
+    Table,
+    Id,
+    ListId,
+    Email,
+    Role,
+    InvitedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    ListId,
+}
@@ -0,0 +1,90 @@
+use crate::m20250731_042456_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(AuditLog::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(AuditLog::ActorId).uuid().null())
+                    .col(
+                        ColumnDef::new(AuditLog::EntityType)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AuditLog::EntityId).uuid().not_null())
+                    .col(ColumnDef::new(AuditLog::Action).string_len(20).not_null())
+                    .col(ColumnDef::new(AuditLog::Changes).json_binary().null())
+                    .col(ColumnDef::new(AuditLog::Ip).string().null())
+                    .col(
+                        ColumnDef::new(AuditLog::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    // The history outlives the accounts it mentions
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AuditLog::Table, AuditLog::ActorId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // GET /todos/{id}/history
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_audit_log_entity")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::EntityType)
+                    .col(AuditLog::EntityId)
+                    .col(AuditLog::CreatedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // GET /auth/me/activity
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_t_audit_log_actor_id")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::ActorId)
+                    .col(AuditLog::CreatedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditLog {
+    #[sea_orm(iden = "t_audit_log")]
+    Table,
+    Id,
+    ActorId,
+    EntityType,
+    EntityId,
+    Action,
+    Changes,
+    Ip,
+    CreatedAt,
+}
@@ -0,0 +1,135 @@
+use crate::m20251018_020000_create_role_tables::Role;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Permission::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Permission::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Permission::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Permission::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT NOW()".to_owned()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePermission::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RolePermission::RoleId).integer().not_null())
+                    .col(
+                        ColumnDef::new(RolePermission::PermissionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RolePermission::RoleId)
+                            .col(RolePermission::PermissionId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RolePermission::Table, RolePermission::RoleId)
+                            .to(Role::Table, Role::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RolePermission::Table, RolePermission::PermissionId)
+                            .to(Permission::Table, Permission::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the built-in permissions, all of them belong to admins
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Permission::Table)
+                    .columns([Permission::Name])
+                    .values_panic(["users:read".into()])
+                    .values_panic(["users:disable".into()])
+                    .values_panic(["users:roles".into()])
+                    .values_panic(["todos:read_any".into()])
+                    .on_conflict(OnConflict::column(Permission::Name).do_nothing().to_owned())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "t_role_permissions" ("role_id", "permission_id")
+                   SELECT "t_roles"."id", "t_permissions"."id" FROM "t_roles", "t_permissions"
+                   WHERE "t_roles"."name" = 'admin'
+                   ON CONFLICT DO NOTHING"#,
+            )
+            .await?;
+
+        // Sign-up gives the user role from now on, existing accounts get it here
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "t_user_roles" ("user_id", "role_id")
+                   SELECT "t_users"."id", "t_roles"."id" FROM "t_users", "t_roles"
+                   WHERE "t_roles"."name" = 'user'
+                   ON CONFLICT DO NOTHING"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RolePermission::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Permission::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Permission {
+    #[sea_orm(iden = "t_permissions")]
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RolePermission {
+    #[sea_orm(iden = "t_role_permissions")]
+    Table,
+    RoleId,
+    PermissionId,
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Due date of the first todo of the series, monthly and yearly occurrences are counted
+        // from it so a series starting on the 31st doesn't drift to the 28th after February.
+        // NULL on the first todo, whose own due date is the start.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todo::SeriesStartAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing series start at their earliest occurrence still in the table
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "t_todos" SET "series_start_at" = (
+                       SELECT MIN("first"."due_at") FROM "t_todos" AS "first"
+                       WHERE "first"."id" = "t_todos"."series_id"
+                          OR "first"."series_id" = "t_todos"."series_id"
+                   )
+                   WHERE "series_id" IS NOT NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todo::Table)
+                    .drop_column(Todo::SeriesStartAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todo {
+    #[sea_orm(iden = "t_todos")]
+    Table,
+    SeriesStartAt,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Emails are looked up ignoring case. Accounts whose emails only differ in case can't be
+        // merged automatically, they have to be resolved by hand before the index can be created.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"DO $$
+                   DECLARE duplicates TEXT;
+                   BEGIN
+                       SELECT string_agg("email", ', ') INTO duplicates
+                       FROM (
+                           SELECT LOWER("email") AS "email" FROM "t_users"
+                           GROUP BY LOWER("email")
+                           HAVING COUNT(*) > 1
+                       ) AS "duplicate";
+
+                       IF duplicates IS NOT NULL THEN
+                           RAISE EXCEPTION 't_users has emails that only differ in case: %', duplicates;
+                       END IF;
+                   END $$"#,
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE UNIQUE INDEX IF NOT EXISTS "idx_t_users_email_lower"
+                   ON "t_users" (LOWER("email"))"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(r#"DROP INDEX IF EXISTS "idx_t_users_email_lower""#)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -5,17 +5,34 @@ use crate::{
     daos::redis_dao::RedisDao,
     models::errors::Error,
     repositories::{
-        refresh_token_repository::RefreshTokenRepository, todo_repository::TodoRepository,
+        audit_log_repository::AuditLogRepository, list_repository::ListRepository,
+        refresh_token_repository::RefreshTokenRepository, tag_repository::TagRepository,
+        todo_item_repository::TodoItemRepository, todo_repository::TodoRepository,
         user_repository::UserRepository,
     },
-    services::{auth_service::AuthService, todo_service::TodoService},
+    services::{
+        audit_service::AuditService, auth_service::AuthService, event_service::EventService,
+        list_service::ListService, mail_service::MailService,
+        notification_service::NotificationService, rate_limit_service::RateLimitService,
+        tag_service::TagService, todo_item_service::TodoItemService, todo_service::TodoService,
+    },
+};
+use redis::{
+    Client, IntoConnectionInfo, ProtocolVersion, PushInfo,
+    aio::{ConnectionManager, ConnectionManagerConfig},
 };
-use redis::{Client, aio::ConnectionManager};
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
 
 pub struct AppState {
     pub auth_service: AuthService,
     pub todo_service: TodoService,
+    pub notification_service: NotificationService,
+    pub tag_service: TagService,
+    pub todo_item_service: TodoItemService,
+    pub list_service: ListService,
+    pub event_service: EventService,
+    pub rate_limit_service: RateLimitService,
 }
 
 impl AppState {
@@ -26,35 +43,81 @@ impl AppState {
         let db_connection = Self::create_database_connection().await?;
 
         // Create Redis connection
-        let redis_connection = Self::create_redis_connection().await?;
+        let (redis_connection, redis_pushes) = Self::create_redis_connection().await?;
         let redis_dao = RedisDao::new(redis_connection.clone());
 
         // Create repositories
         let (user_repo, refresh_repo, todo_repo) = Self::create_repositories(&db_connection);
 
         // Create services
-        let (auth_service, todo_service) =
-            Self::create_services(user_repo, refresh_repo, todo_repo, redis_dao).await?;
+        let notification_service = NotificationService::new(redis_dao.clone());
+        let rate_limit_service = RateLimitService::new(redis_dao.clone());
+        let tag_service =
+            TagService::new(TagRepository::new(db_connection.clone()), todo_repo.clone());
+        let todo_item_repo = TodoItemRepository::new(db_connection.clone());
+        let list_service = ListService::new(
+            ListRepository::new(db_connection.clone()),
+            user_repo.clone(),
+        );
+        let audit_service = AuditService::new(AuditLogRepository::new(db_connection.clone()));
+        let event_service = EventService::new(redis_dao.clone(), redis_pushes);
+        event_service.subscribe_channel().await?;
+        let auth_service = AuthService::new(
+            user_repo,
+            refresh_repo,
+            audit_service.clone(),
+            notification_service.clone(),
+            MailService::from_config()?,
+            redis_dao.clone(),
+        );
+        let todo_service = TodoService::new(
+            todo_repo,
+            todo_item_repo.clone(),
+            audit_service,
+            event_service.clone(),
+            redis_dao,
+        )?;
+        let todo_item_service = TodoItemService::new(todo_item_repo, todo_service.clone());
 
         log::info!("Application state initialized successfully");
 
         Ok(AppState {
             auth_service,
             todo_service,
+            notification_service,
+            tag_service,
+            todo_item_service,
+            list_service,
+            event_service,
+            rate_limit_service,
         })
     }
 
-    async fn create_redis_connection() -> Result<ConnectionManager, Error> {
+    // Pub/sub messages of the connection are delivered to the returned receiver. Subscribing on
+    // a multiplexed connection needs RESP3, so it is used whatever REDIS_URL says.
+    async fn create_redis_connection()
+    -> Result<(ConnectionManager, UnboundedReceiver<PushInfo>), Error> {
         log::info!("Connecting to Redis...");
-        let client = Client::open(config::REDIS_URL.to_string())
+        let mut connection_info = config::REDIS_URL
+            .as_str()
+            .into_connection_info()
             .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        connection_info.redis.protocol = ProtocolVersion::RESP3;
+
+        let client =
+            Client::open(connection_info).map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        let (push_sender, push_receiver) = mpsc::unbounded_channel();
+        let manager_config = ConnectionManagerConfig::new()
+            .set_push_sender(push_sender)
+            .set_automatic_resubscription();
 
-        let manager = ConnectionManager::new(client)
+        let manager = ConnectionManager::new_with_config(client, manager_config)
             .await
             .map_err(|e| Error::InternalServerError(e.to_string()))?;
 
         log::info!("Connected to Redis successfully");
-        Ok(manager)
+        Ok((manager, push_receiver))
     }
 
     async fn create_database_connection() -> Result<DatabaseConnection, Error> {
@@ -84,16 +147,4 @@ impl AppState {
 
         (user_repository, refresh_token_repository, todo_repository)
     }
-
-    async fn create_services(
-        user_repo: UserRepository,
-        refresh_repo: RefreshTokenRepository,
-        todo_repo: TodoRepository,
-        redis_dao: RedisDao,
-    ) -> Result<(AuthService, TodoService), Error> {
-        let auth_service = AuthService::new(user_repo, refresh_repo);
-        let todo_service = TodoService::new(todo_repo, redis_dao)?;
-
-        Ok((auth_service, todo_service))
-    }
 }
@@ -1,5 +1,7 @@
+use crate::utils::common::ProxyHeader;
+use ipnet::IpNet;
 use once_cell::sync::Lazy;
-use std::env;
+use std::{env, net::IpAddr};
 
 pub static PORT: Lazy<u16> = Lazy::new(|| {
     env::var("PORT")
@@ -27,6 +29,30 @@ pub static DB_MAX_CONNECTIONS: Lazy<u32> = Lazy::new(|| {
         .expect("DB_MAX_CONNECTIONS must be a valid number")
 });
 
+// Comma separated CIDRs (or single IPs) of the reverse proxies whose PROXY_HEADER is believed. Empty means the client IP is always the peer address.
+pub static TRUSTED_PROXIES: Lazy<Vec<IpNet>> = Lazy::new(|| {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .map(|proxy| {
+            proxy
+                .parse::<IpNet>()
+                .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from))
+                .expect("TRUSTED_PROXIES must be a list of CIDRs or IPs")
+        })
+        .collect()
+});
+
+// The one header TRUSTED_PROXIES write the client address to: x-forwarded-for, forwarded or
+// x-real-ip
+pub static PROXY_HEADER: Lazy<ProxyHeader> = Lazy::new(|| {
+    let header = env::var("PROXY_HEADER").unwrap_or_else(|_| "x-forwarded-for".to_string());
+    ProxyHeader::parse(&header)
+        .expect("PROXY_HEADER must be one of x-forwarded-for, forwarded, x-real-ip")
+});
+
 pub static RATE_LIMIT_MAX_REQUESTS: Lazy<usize> = Lazy::new(|| {
     env::var("RATE_LIMIT_MAX_REQUESTS")
         .unwrap_or_else(|_| "100".to_string())
@@ -41,6 +67,113 @@ pub static RATE_LIMIT_WINDOW_SECS: Lazy<u64> = Lazy::new(|| {
         .expect("RATE_LIMIT_WINDOW_SECS must be a valid number")
 });
 
+// Failed sign-ins of an email before it is locked, the attempts before that are slowed down
+// with an exponential backoff starting at SIGNIN_BACKOFF_BASE_SECS
+pub static SIGNIN_MAX_FAILED_ATTEMPTS: Lazy<i64> = Lazy::new(|| {
+    env::var("SIGNIN_MAX_FAILED_ATTEMPTS")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse()
+        .expect("SIGNIN_MAX_FAILED_ATTEMPTS must be a valid number")
+});
+
+// Failed sign-ins from one IP, across all emails, before the IP is locked
+pub static SIGNIN_MAX_FAILED_ATTEMPTS_PER_IP: Lazy<i64> = Lazy::new(|| {
+    env::var("SIGNIN_MAX_FAILED_ATTEMPTS_PER_IP")
+        .unwrap_or_else(|_| "20".to_string())
+        .parse()
+        .expect("SIGNIN_MAX_FAILED_ATTEMPTS_PER_IP must be a valid number")
+});
+
+pub static SIGNIN_BACKOFF_BASE_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("SIGNIN_BACKOFF_BASE_SECS")
+        .unwrap_or_else(|_| "1".to_string())
+        .parse()
+        .expect("SIGNIN_BACKOFF_BASE_SECS must be a valid number")
+});
+
+// How long a lockout lasts, failures are also forgotten after this long without a new one
+pub static SIGNIN_LOCKOUT_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("SIGNIN_LOCKOUT_SECS")
+        .unwrap_or_else(|_| "900".to_string())
+        .parse()
+        .expect("SIGNIN_LOCKOUT_SECS must be a valid number")
+});
+
+// Sign-in is refused until the email address is verified
+pub static EMAIL_VERIFICATION_REQUIRED: Lazy<bool> = Lazy::new(|| {
+    env::var("EMAIL_VERIFICATION_REQUIRED")
+        .unwrap_or_else(|_| "false".to_string())
+        .parse()
+        .expect("EMAIL_VERIFICATION_REQUIRED must be true or false")
+});
+
+pub static EMAIL_VERIFICATION_TOKEN_EXPIRATION_HOURS: Lazy<i64> = Lazy::new(|| {
+    env::var("EMAIL_VERIFICATION_TOKEN_EXPIRATION_HOURS")
+        .unwrap_or_else(|_| "24".to_string())
+        .parse()
+        .expect("EMAIL_VERIFICATION_TOKEN_EXPIRATION_HOURS must be a valid number")
+});
+
+// Page of the frontend that posts the token to /auth/verify-email, `?token=` is appended
+pub static EMAIL_VERIFICATION_URL: Lazy<String> = Lazy::new(|| {
+    env::var("EMAIL_VERIFICATION_URL")
+        .unwrap_or_else(|_| "http://localhost:3000/verify-email".to_string())
+});
+
+pub static PASSWORD_RESET_TOKEN_EXPIRATION_MINUTES: Lazy<i64> = Lazy::new(|| {
+    env::var("PASSWORD_RESET_TOKEN_EXPIRATION_MINUTES")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse()
+        .expect("PASSWORD_RESET_TOKEN_EXPIRATION_MINUTES must be a valid number")
+});
+
+// Page of the frontend that posts the token and new password to /auth/reset-password
+pub static PASSWORD_RESET_URL: Lazy<String> = Lazy::new(|| {
+    env::var("PASSWORD_RESET_URL")
+        .unwrap_or_else(|_| "http://localhost:3000/reset-password".to_string())
+});
+
+// "file" logs mails (and saves them to MAIL_FILE_DIR when set), "smtp" sends them
+pub static MAILER: Lazy<String> =
+    Lazy::new(|| env::var("MAILER").unwrap_or_else(|_| "file".to_string()));
+
+pub static MAIL_FROM: Lazy<String> =
+    Lazy::new(|| env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string()));
+
+pub static MAIL_FILE_DIR: Lazy<Option<String>> =
+    Lazy::new(|| env::var("MAIL_FILE_DIR").ok().filter(|dir| !dir.is_empty()));
+
+pub static SMTP_HOST: Lazy<String> =
+    Lazy::new(|| env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()));
+
+pub static SMTP_PORT: Lazy<u16> = Lazy::new(|| {
+    env::var("SMTP_PORT")
+        .unwrap_or_else(|_| "587".to_string())
+        .parse()
+        .expect("SMTP_PORT must be a valid number")
+});
+
+// "starttls", "tls" or "none"
+pub static SMTP_SECURITY: Lazy<String> =
+    Lazy::new(|| env::var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string()));
+
+// Limit for each step of an SMTP session: connecting, the TLS handshake, every command
+pub static SMTP_TIMEOUT_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("SMTP_TIMEOUT_SECS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .expect("SMTP_TIMEOUT_SECS must be a positive number")
+});
+
+// Only sent over TLS, so SMTP_SECURITY can't be "none" when they are set
+pub static SMTP_USERNAME: Lazy<Option<String>> =
+    Lazy::new(|| env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()));
+
+pub static SMTP_PASSWORD: Lazy<Option<String>> =
+    Lazy::new(|| env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()));
+
 pub static REFRESH_TOKEN_EXPIRATION_HOURS: Lazy<i64> = Lazy::new(|| {
     env::var("REFRESH_TOKEN_EXPIRATION_HOURS")
         .unwrap_or_else(|_| "24".to_string())
@@ -68,3 +201,54 @@ pub static DEFAULT_PAGE: Lazy<u64> = Lazy::new(|| {
         .parse()
         .expect("DEFAULT_PAGE must be a valid number")
 });
+
+pub static TODO_TRASH_RETENTION_DAYS: Lazy<i64> = Lazy::new(|| {
+    env::var("TODO_TRASH_RETENTION_DAYS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse()
+        .expect("TODO_TRASH_RETENTION_DAYS must be a valid number")
+});
+
+pub static TODO_TRASH_PURGE_CRON: Lazy<String> =
+    Lazy::new(|| env::var("TODO_TRASH_PURGE_CRON").unwrap_or_else(|_| "0 0 3 * * *".to_string()));
+
+pub static TODO_REMINDER_CRON: Lazy<String> =
+    Lazy::new(|| env::var("TODO_REMINDER_CRON").unwrap_or_else(|_| "0 * * * * *".to_string()));
+
+pub static NOTIFICATION_QUEUE_KEY: Lazy<String> = Lazy::new(|| {
+    env::var("NOTIFICATION_QUEUE_KEY").unwrap_or_else(|_| "notifications:queue".to_string())
+});
+
+// Pub/sub channel that fans todo changes out to the event streams of every instance
+pub static TODO_EVENTS_CHANNEL: Lazy<String> =
+    Lazy::new(|| env::var("TODO_EVENTS_CHANNEL").unwrap_or_else(|_| "todos:events".to_string()));
+
+pub static TODO_EVENTS_KEEP_ALIVE_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("TODO_EVENTS_KEEP_ALIVE_SECS")
+        .unwrap_or_else(|_| "15".to_string())
+        .parse()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .expect("TODO_EVENTS_KEEP_ALIVE_SECS must be a positive number")
+});
+
+// How often an open event stream checks that its token wasn't revoked and the user is enabled
+pub static TODO_EVENTS_SESSION_CHECK_SECS: Lazy<u64> = Lazy::new(|| {
+    env::var("TODO_EVENTS_SESSION_CHECK_SECS")
+        .unwrap_or_else(|_| "60".to_string())
+        .parse()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .expect("TODO_EVENTS_SESSION_CHECK_SECS must be a positive number")
+});
+
+pub static TODO_RECURRENCE_CRON: Lazy<String> =
+    Lazy::new(|| env::var("TODO_RECURRENCE_CRON").unwrap_or_else(|_| "0 */15 * * * *".to_string()));
+
+// How far ahead occurrences of recurring todos are created
+pub static TODO_RECURRENCE_HORIZON_DAYS: Lazy<i64> = Lazy::new(|| {
+    env::var("TODO_RECURRENCE_HORIZON_DAYS")
+        .unwrap_or_else(|_| "7".to_string())
+        .parse()
+        .expect("TODO_RECURRENCE_HORIZON_DAYS must be a valid number")
+});
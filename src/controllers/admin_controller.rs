@@ -0,0 +1,136 @@
+use crate::{
+    app_state::AppState,
+    handle_response,
+    middlewares::{
+        auth_middleware::auth_middleware,
+        role_middleware::{require_permission, require_role},
+    },
+    models::*,
+    utils::{common::ClientIp, jwt::AuthenticatedUser},
+};
+use actix_web::{
+    Responder, delete, get,
+    middleware::from_fn,
+    post, put,
+    web::{Data, Path, Query, ServiceConfig, scope},
+};
+use actix_web_validation::Validated;
+
+#[get("/users", wrap = "from_fn(require_permission(\"users:read\"))")]
+async fn get_all_users(
+    app_state: Data<AppState>,
+    Validated(params): Validated<Query<request::GetAllUsersRequest>>,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .get_all_users(params.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[post(
+    "/users/{id}/disable",
+    wrap = "from_fn(require_permission(\"users:disable\"))"
+)]
+async fn disable_user(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .set_user_disabled(path.into_inner(), true, user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[post(
+    "/users/{id}/enable",
+    wrap = "from_fn(require_permission(\"users:disable\"))"
+)]
+async fn enable_user(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .set_user_disabled(path.into_inner(), false, user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[get(
+    "/users/{id}/todos",
+    wrap = "from_fn(require_permission(\"todos:read_any\"))"
+)]
+async fn get_user_todos(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(params): Validated<Query<request::GetAllTodosRequest>>,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .get_user_todos(path.into_inner(), params.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[get("/roles", wrap = "from_fn(require_permission(\"users:roles\"))")]
+async fn get_roles(app_state: Data<AppState>) -> impl Responder {
+    let result = app_state.auth_service.get_roles().await;
+    handle_response!(result)
+}
+
+#[put(
+    "/users/{id}/roles/{role}",
+    wrap = "from_fn(require_permission(\"users:roles\"))"
+)]
+async fn grant_role(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let (id, role) = path.into_inner();
+    let result = app_state
+        .auth_service
+        .set_user_role(id, role, true, user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[delete(
+    "/users/{id}/roles/{role}",
+    wrap = "from_fn(require_permission(\"users:roles\"))"
+)]
+async fn revoke_role(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let (id, role) = path.into_inner();
+    let result = app_state
+        .auth_service
+        .set_user_role(id, role, false, user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/admin")
+            .wrap(from_fn(require_role("admin")))
+            .wrap(from_fn(auth_middleware))
+            .service(get_all_users)
+            .service(disable_user)
+            .service(enable_user)
+            .service(get_user_todos)
+            .service(get_roles)
+            .service(grant_role)
+            .service(revoke_role),
+    );
+}
@@ -1,15 +1,17 @@
 use crate::{
-    app_state::AppState, handle_response, middlewares::auth_middleware::auth_middleware, models::*,
-    utils::jwt::AuthenticatedUser,
+    app_state::AppState,
+    handle_response,
+    middlewares::auth_middleware::auth_middleware,
+    models::{errors::ErrorToHttp, *},
+    utils::{common::ClientIp, jwt::AuthenticatedUser, merge_patch::require_merge_patch},
 };
 use actix_web::{
-    Responder, get,
+    HttpRequest, Responder, get,
     middleware::from_fn,
-    post, put,
-    web::{Data, Json, ServiceConfig, scope},
+    patch, post, put,
+    web::{Data, Json, Query, ServiceConfig, scope},
 };
 use actix_web_validation::Validated;
-use uuid::Uuid;
 
 #[post("/signup")]
 async fn sign_up(
@@ -26,27 +28,116 @@ async fn sign_in(
     app_state: Data<AppState>,
     Validated(body): Validated<Json<request::SignInRequest>>,
     // Validated để validate request body, throw error từ main > app.validator_error_handler(Arc::new(validator_error_handler))
+    ip: ClientIp,
 ) -> impl Responder {
-    let result = app_state.auth_service.authenticate(body.into_inner()).await;
+    let result = app_state
+        .auth_service
+        .authenticate(body.into_inner(), ip.0)
+        .await;
     handle_response!(result)
 }
 
 /// Get current user information
 #[get("/me")]
+#[allow(clippy::clone_on_copy)]
 async fn me(app_state: Data<AppState>, user: AuthenticatedUser) -> impl Responder {
     let result = app_state.auth_service.me(user.sub.clone()).await;
     handle_response!(result)
 }
 
 #[put("/update")]
+#[allow(clippy::clone_on_copy)]
 async fn update(
     app_state: Data<AppState>,
     user: AuthenticatedUser,
     Validated(body): Validated<Json<request::UpdateUserRequest>>,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .update(user.sub.clone(), body.into_inner(), ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[patch("/me")]
+async fn patch_me(
+    app_state: Data<AppState>,
+    user: AuthenticatedUser,
+    Validated(body): Validated<Json<request::PatchUserRequest>>,
+    req: HttpRequest,
+    ip: ClientIp,
+) -> impl Responder {
+    if let Err(error) = require_merge_patch(&req) {
+        return error.to_http_response();
+    }
+
+    let result = app_state
+        .auth_service
+        .patch(user.sub, body.into_inner(), ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[get("/me/activity")]
+async fn get_activity(
+    app_state: Data<AppState>,
+    user: AuthenticatedUser,
+    Validated(params): Validated<Query<request::GetAuditLogRequest>>,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .get_activity(user.sub, params.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[post("/verify-email")]
+async fn verify_email(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::VerifyEmailRequest>>,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .verify_email(body.into_inner(), ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/resend-verification")]
+async fn resend_verification(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::ResendVerificationRequest>>,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .resend_verification(body.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[post("/forgot-password")]
+async fn forgot_password(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::ForgotPasswordRequest>>,
+) -> impl Responder {
+    let result = app_state
+        .auth_service
+        .forgot_password(body.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[post("/reset-password")]
+async fn reset_password(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::ResetPasswordRequest>>,
+    ip: ClientIp,
 ) -> impl Responder {
     let result = app_state
         .auth_service
-        .update(user.sub.clone(), body.into_inner())
+        .reset_password(body.into_inner(), ip.0)
         .await;
     handle_response!(result)
 }
@@ -57,21 +148,52 @@ async fn refresh_token(
     Validated(body): Validated<Json<request::RefreshTokenRequest>>,
 ) -> impl Responder {
     let body = body.into_inner();
-    let refresh_token = Uuid::parse_str(&body.refresh_token).unwrap_or_else(|_| Uuid::nil());
-    let result = app_state.auth_service.refresh_token(refresh_token).await;
+    let result = app_state
+        .auth_service
+        .refresh_token(body.refresh_token)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/logout")]
+async fn logout(
+    app_state: Data<AppState>,
+    user: AuthenticatedUser,
+    Validated(body): Validated<Json<request::RefreshTokenRequest>>,
+) -> impl Responder {
+    let body = body.into_inner();
+    let result = app_state
+        .auth_service
+        .logout(user.0, body.refresh_token)
+        .await;
     handle_response!(result)
 }
+
+#[post("/logout-all")]
+async fn logout_all(app_state: Data<AppState>, user: AuthenticatedUser) -> impl Responder {
+    let result = app_state.auth_service.logout_all(user.0).await;
+    handle_response!(result)
+}
+
 pub fn config(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/auth")
             .service(sign_up)
             .service(sign_in)
             .service(refresh_token)
+            .service(verify_email)
+            .service(resend_verification)
+            .service(forgot_password)
+            .service(reset_password)
             .service(
                 scope("")
                     .wrap(from_fn(auth_middleware))
                     .service(me)
-                    .service(update),
+                    .service(update)
+                    .service(patch_me)
+                    .service(get_activity)
+                    .service(logout)
+                    .service(logout_all),
             ),
     );
 }
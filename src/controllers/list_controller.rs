@@ -0,0 +1,213 @@
+use crate::{
+    app_state::AppState, handle_response, middlewares::auth_middleware::auth_middleware, models::*,
+    utils::jwt::AuthenticatedUser,
+};
+use actix_web::{
+    Responder, delete, get,
+    middleware::from_fn,
+    post, put,
+    web::{Data, Json, Path, ServiceConfig, scope},
+};
+use actix_web_validation::Validated;
+
+#[get("")]
+async fn get_lists(app_state: Data<AppState>, user: AuthenticatedUser) -> impl Responder {
+    let result = app_state.list_service.get_lists(user.sub).await;
+    handle_response!(result)
+}
+
+#[post("")]
+async fn create_list(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::CreateListRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .create_list(body.into_inner(), user.sub)
+        .await;
+    handle_response!(result, StatusCode::CREATED)
+}
+
+// Invitations received by the current user, registered before the /{id} routes
+#[get("/invitations")]
+async fn get_received_invitations(
+    app_state: Data<AppState>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .get_received_invitations(user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/invitations/{id}/accept")]
+async fn accept_invitation(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .accept_invitation(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/invitations/{id}/decline")]
+async fn decline_invitation(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .decline_invitation(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[get("/{id}")]
+async fn get_list(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .get_list(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[put("/{id}")]
+async fn update_list(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::UpdateListRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .update_list(path.into_inner(), body.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[delete("/{id}")]
+async fn delete_list(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .delete_list(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[get("/{id}/members")]
+async fn get_members(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .get_members(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[put("/{id}/members/{user_id}")]
+async fn update_member(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    Validated(body): Validated<Json<request::UpdateListMemberRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let (id, member_id) = path.into_inner();
+    let result = app_state
+        .list_service
+        .update_member(id, member_id, body.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[delete("/{id}/members/{user_id}")]
+async fn remove_member(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let (id, member_id) = path.into_inner();
+    let result = app_state
+        .list_service
+        .remove_member(id, member_id, user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[get("/{id}/invitations")]
+async fn get_invitations(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .get_invitations(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/{id}/invitations")]
+async fn invite_member(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::InviteListMemberRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .list_service
+        .invite_member(path.into_inner(), body.into_inner(), user.sub)
+        .await;
+    handle_response!(result, StatusCode::CREATED)
+}
+
+#[delete("/{id}/invitations/{invitation_id}")]
+async fn revoke_invitation(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let (id, invitation_id) = path.into_inner();
+    let result = app_state
+        .list_service
+        .revoke_invitation(id, invitation_id, user.sub)
+        .await;
+    handle_response!(result)
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/lists")
+            .wrap(from_fn(auth_middleware))
+            .service(get_lists)
+            .service(create_list)
+            .service(get_received_invitations)
+            .service(accept_invitation)
+            .service(decline_invitation)
+            .service(get_list)
+            .service(update_list)
+            .service(delete_list)
+            .service(get_members)
+            .service(update_member)
+            .service(remove_member)
+            .service(get_invitations)
+            .service(invite_member)
+            .service(revoke_invitation),
+    );
+}
@@ -1,4 +1,7 @@
+pub mod admin_controller;
 pub mod auth_controller;
 pub mod home_controller;
+pub mod list_controller;
 pub mod not_found_controller;
-pub mod todo_controller;
\ No newline at end of file
+pub mod tag_controller;
+pub mod todo_controller;
@@ -0,0 +1,82 @@
+use crate::{
+    app_state::AppState, handle_response, middlewares::auth_middleware::auth_middleware, models::*,
+    utils::jwt::AuthenticatedUser,
+};
+use actix_web::{
+    Responder, delete, get,
+    middleware::from_fn,
+    post, put,
+    web::{Data, Json, Path, ServiceConfig, scope},
+};
+use actix_web_validation::Validated;
+
+#[get("")]
+async fn get_all_tags(app_state: Data<AppState>, user: AuthenticatedUser) -> impl Responder {
+    let result = app_state.tag_service.get_all_tags(user.sub).await;
+    handle_response!(result)
+}
+
+#[get("/{id}")]
+async fn get_tag_by_id(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .get_tag_by_id(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[post("")]
+async fn create_tag(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::CreateTagRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .create_tag(body.into_inner(), user.sub)
+        .await;
+    handle_response!(result, StatusCode::CREATED)
+}
+
+#[put("/{id}")]
+async fn update_tag(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::UpdateTagRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .update_tag(path.into_inner(), body.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[delete("/{id}")]
+async fn delete_tag(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .delete_tag(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/tags")
+            .wrap(from_fn(auth_middleware))
+            .service(get_all_tags)
+            .service(get_tag_by_id)
+            .service(create_tag)
+            .service(update_tag)
+            .service(delete_tag),
+    );
+}
@@ -1,52 +1,173 @@
 use crate::{
-    app_state::AppState, handle_response, middlewares::auth_middleware::auth_middleware, models::*,
-    utils::jwt::AuthenticatedUser,
+    app_state::AppState,
+    handle_response,
+    middlewares::auth_middleware::auth_middleware,
+    models::{errors::ErrorToHttp, *},
+    utils::{
+        common::ClientIp, etag, jwt::AuthenticatedUser, merge_patch::require_merge_patch,
+        sse::todo_event_stream,
+    },
 };
 use actix_web::{
-    Responder, delete, get,
+    HttpRequest, HttpResponse, Responder, delete, get,
+    http::header::{CACHE_CONTROL, ContentEncoding, ETag},
     middleware::from_fn,
-    post, put,
+    patch, post, put,
     web::{Data, Json, Path, Query, ServiceConfig, scope},
 };
 use actix_web_validation::Validated;
+use chrono::Utc;
+use entity::t_todos;
+use std::time::Duration;
+
+// Like handle_response!, but also returns the version of the todo as its ETag
+fn todo_response(result: Result<t_todos::Model, errors::Error>) -> HttpResponse {
+    match result {
+        Ok(todo) => HttpResponse::Ok()
+            .insert_header(ETag(etag::todo_etag(todo.version)))
+            .json(todo),
+        Err(error) => error.to_http_response(),
+    }
+}
 
 #[get("")]
 async fn get_all_todos(
     app_state: Data<AppState>,
     Validated(params): Validated<Query<request::GetAllTodosRequest>>,
+    user: AuthenticatedUser,
 ) -> impl Responder {
     let result = app_state
         .todo_service
-        .get_all_todos(params.into_inner())
+        .get_all_todos(params.into_inner(), user.sub)
         .await;
     handle_response!(result)
 }
 
-#[get("/{id}")]
-async fn get_todo_by_id(app_state: Data<AppState>, path: Path<String>) -> impl Responder {
+#[get("/trash")]
+async fn get_trash(
+    app_state: Data<AppState>,
+    Validated(params): Validated<Query<request::GetTrashTodosRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
     let result = app_state
         .todo_service
-        .get_todo_by_id(path.into_inner())
+        .get_trash(params.into_inner(), user.sub)
         .await;
     handle_response!(result)
 }
 
+// Server-Sent Events of the changes to the user's todos, registered before get_todo_by_id
+#[get("/events")]
+async fn todo_events(app_state: Data<AppState>, user: AuthenticatedUser) -> HttpResponse {
+    let receiver = app_state.event_service.subscribe();
+    let claims = user.0;
+    let expires_in = (claims.exp as u64).saturating_sub(Utc::now().timestamp().max(0) as u64);
+    let user_id = claims.sub;
+
+    // A Redis or database failure ends the stream too, the client reconnects through auth
+    let auth_service = app_state.auth_service.clone();
+    let is_session_active = move || {
+        let (auth_service, claims) = (auth_service.clone(), claims.clone());
+        async move {
+            auth_service
+                .is_session_active(&claims)
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("failed to check the session of todo events: {:?}", e);
+                    false
+                })
+        }
+    };
+
+    // Identity encoding keeps the compression middleware from buffering the events
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .insert_header(ContentEncoding::Identity)
+        .streaming(todo_event_stream(
+            receiver,
+            user_id,
+            Duration::from_secs(expires_in),
+            is_session_active,
+        ))
+}
+
+#[get("/{id}")]
+async fn get_todo_by_id(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+    req: HttpRequest,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .get_todo_by_id(path.into_inner(), user.sub)
+        .await;
+
+    if let Ok(todo) = &result
+        && etag::is_not_modified(&req, todo.version)
+    {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag::todo_etag(todo.version)))
+            .finish();
+    }
+
+    todo_response(result)
+}
+
 #[post("")]
 async fn create_todo(
     app_state: Data<AppState>,
     Validated(body): Validated<Json<request::CreateTodoRequest>>,
     user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .create_todo(body.into_inner(), user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/batch")]
+async fn batch_todos(
+    app_state: Data<AppState>,
+    Validated(body): Validated<Json<request::BatchTodosRequest>>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
 ) -> impl Responder {
     let result = app_state
         .todo_service
-        .create_todo(body.into_inner(), user.sub.clone())
+        .batch_todos(body.into_inner(), user.sub, ip.0)
         .await;
     handle_response!(result)
 }
 
 #[delete("/{id}")]
-async fn delete_todo(app_state: Data<AppState>, path: Path<String>) -> impl Responder {
-    let result = app_state.todo_service.delete_todo(path.into_inner()).await;
+async fn delete_todo(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .delete_todo(path.into_inner(), user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/{id}/restore")]
+async fn restore_todo(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .restore_todo(path.into_inner(), user.sub, ip.0)
+        .await;
     handle_response!(result)
 }
 
@@ -55,10 +176,160 @@ async fn update_todo(
     app_state: Data<AppState>,
     path: Path<String>,
     Validated(body): Validated<Json<request::UpdateTodoRequest>>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+    req: HttpRequest,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .update_todo(
+            path.into_inner(),
+            body.into_inner(),
+            user.sub,
+            etag::if_match_versions(&req),
+            ip.0,
+        )
+        .await;
+    todo_response(result)
+}
+
+#[patch("/{id}")]
+async fn patch_todo(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::PatchTodoRequest>>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+    req: HttpRequest,
 ) -> impl Responder {
+    if let Err(error) = require_merge_patch(&req) {
+        return error.to_http_response();
+    }
+
     let result = app_state
         .todo_service
-        .update_todo(path.into_inner(), body.into_inner())
+        .patch_todo(
+            path.into_inner(),
+            body.into_inner(),
+            user.sub,
+            etag::if_match_versions(&req),
+            ip.0,
+        )
+        .await;
+    todo_response(result)
+}
+
+#[get("/{id}/history")]
+async fn get_todo_history(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(params): Validated<Query<request::GetAuditLogRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .todo_service
+        .get_todo_history(path.into_inner(), user.sub, params.into_inner())
+        .await;
+    handle_response!(result)
+}
+
+#[get("/{id}/tags")]
+async fn get_todo_tags(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .get_todo_tags(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[put("/{id}/tags")]
+async fn set_todo_tags(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::SetTodoTagsRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .tag_service
+        .set_todo_tags(path.into_inner(), body.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[get("/{id}/items")]
+async fn get_todo_items(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .todo_item_service
+        .get_items(path.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[post("/{id}/items")]
+async fn create_todo_item(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::CreateTodoItemRequest>>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let result = app_state
+        .todo_item_service
+        .create_item(path.into_inner(), body.into_inner(), user.sub, ip.0)
+        .await;
+    handle_response!(result, StatusCode::CREATED)
+}
+
+// Registered before update_todo_item so "order" isn't taken for an item id
+#[put("/{id}/items/order")]
+async fn reorder_todo_items(
+    app_state: Data<AppState>,
+    path: Path<String>,
+    Validated(body): Validated<Json<request::ReorderTodoItemsRequest>>,
+    user: AuthenticatedUser,
+) -> impl Responder {
+    let result = app_state
+        .todo_item_service
+        .reorder_items(path.into_inner(), body.into_inner(), user.sub)
+        .await;
+    handle_response!(result)
+}
+
+#[put("/{id}/items/{item_id}")]
+async fn update_todo_item(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    Validated(body): Validated<Json<request::UpdateTodoItemRequest>>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let (id, item_id) = path.into_inner();
+    let result = app_state
+        .todo_item_service
+        .update_item(id, item_id, body.into_inner(), user.sub, ip.0)
+        .await;
+    handle_response!(result)
+}
+
+#[delete("/{id}/items/{item_id}")]
+async fn delete_todo_item(
+    app_state: Data<AppState>,
+    path: Path<(String, String)>,
+    user: AuthenticatedUser,
+    ip: ClientIp,
+) -> impl Responder {
+    let (id, item_id) = path.into_inner();
+    let result = app_state
+        .todo_item_service
+        .delete_item(id, item_id, user.sub, ip.0)
         .await;
     handle_response!(result)
 }
@@ -75,9 +346,22 @@ pub fn config(cfg: &mut ServiceConfig) {
             .wrap(from_fn(auth_middleware))
             .service(get_all_todos)
             .service(get_external_data)
+            .service(get_trash)
+            .service(todo_events)
             .service(get_todo_by_id)
             .service(create_todo)
+            .service(batch_todos)
             .service(delete_todo)
-            .service(update_todo),
+            .service(restore_todo)
+            .service(update_todo)
+            .service(patch_todo)
+            .service(get_todo_history)
+            .service(get_todo_tags)
+            .service(set_todo_tags)
+            .service(get_todo_items)
+            .service(create_todo_item)
+            .service(reorder_todo_items)
+            .service(update_todo_item)
+            .service(delete_todo_item),
     );
 }
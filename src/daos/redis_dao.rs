@@ -1,6 +1,6 @@
 use async_trait::async_trait;
-use redis::AsyncCommands;
 use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, FromRedisValue, Script};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
@@ -42,6 +42,7 @@ pub enum Value {
 }
 
 impl Value {
+    #[allow(clippy::useless_format)]
     pub fn to_string_with_type(&self) -> String {
         match self {
             Value::Undefined => format!("a"),
@@ -109,6 +110,7 @@ pub struct RedisDao {
     pub connection: ConnectionManager,
 }
 
+#[allow(dead_code)]
 impl RedisDao {
     pub fn new(connection: ConnectionManager) -> Self {
         Self { connection }
@@ -212,29 +214,79 @@ impl RedisDao {
         Ok(typed_value)
     }
 
+    pub async fn set_value_with_expiry(
+        &mut self,
+        key: &str,
+        value: &Value,
+        seconds: u64,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let value_string = value.to_string_with_type();
+        let _: () = self.connection.set_ex(key, value_string, seconds).await?;
+        Ok(())
+    }
+
     pub async fn del(&mut self, key: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
         let _: usize = self.connection.del(key).await?;
         Ok(())
     }
 
+    // Deletes the key and tells whether it existed, so only one caller can take it
+    pub async fn take(&mut self, key: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let deleted: usize = self.connection.del(key).await?;
+        Ok(deleted > 0)
+    }
+
     pub async fn exists(&mut self, key: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
         let result: i32 = self.connection.exists(key).await?;
         Ok(result > 0)
     }
 
-    pub async fn expire(
+    pub async fn ttl(&mut self, key: &str) -> Result<i32, Box<dyn Error + Send + Sync>> {
+        let result: i32 = self.connection.ttl(key).await?;
+        Ok(result)
+    }
+
+    // Appends to the tail of a list, used as a FIFO queue together with LPOP
+    pub async fn rpush(
         &mut self,
         key: &str,
-        seconds: u64,
+        value: &str,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let _: bool = self.connection.expire(key, seconds as i64).await?;
+        let _: usize = self.connection.rpush(key, value).await?;
         Ok(())
     }
 
-    pub async fn ttl(&mut self, key: &str) -> Result<i32, Box<dyn Error + Send + Sync>> {
-        let result: i32 = self.connection.ttl(key).await?;
+    // Redis caches the script by its hash, later calls only send the hash
+    pub async fn eval_script<T>(
+        &mut self,
+        script: &Script,
+        keys: &[&str],
+        args: &[&str],
+    ) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        T: FromRedisValue,
+    {
+        let mut invocation = script.prepare_invoke();
+        for key in keys {
+            invocation.key(*key);
+        }
+        for arg in args {
+            invocation.arg(*arg);
+        }
+
+        let result: T = invocation.invoke_async(&mut self.connection).await?;
         Ok(result)
     }
+
+    // Returns the number of clients that received the message
+    pub async fn publish(
+        &mut self,
+        channel: &str,
+        message: &str,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let receivers: usize = self.connection.publish(channel, message).await?;
+        Ok(receivers)
+    }
 }
 
 #[async_trait]
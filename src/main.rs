@@ -17,10 +17,13 @@ use actix_web::{
 };
 use actix_web_validation::validator::ValidatorErrorHandlerExt;
 use app_state::AppState;
-use controllers::{auth_controller, home_controller, not_found_controller, todo_controller};
+use controllers::{
+    admin_controller, auth_controller, home_controller, list_controller, not_found_controller,
+    tag_controller, todo_controller,
+};
 use dotenv::dotenv;
 use env_logger::Env;
-use middlewares::rate_limit_middleware::rate_limiter_middleware;
+use middlewares::rate_limit_middleware::rate_limit_middleware;
 use models::errors::Error;
 use services::job_service::JobService;
 use std::sync::Arc;
@@ -36,7 +39,11 @@ async fn main() -> Result<(), Error> {
     let app_state = AppState::new().await?;
 
     // Init job service (runs independently)
-    let job_service = JobService::new().await?;
+    let job_service = JobService::new(
+        app_state.todo_service.todo_repository.clone(),
+        app_state.notification_service.clone(),
+    )
+    .await?;
     job_service.start().await?;
 
     // App data
@@ -48,13 +55,16 @@ async fn main() -> Result<(), Error> {
             .wrap(actix_web_middleware::Compress::default())
             .wrap(Cors::permissive()) // allow all origins
             .wrap(actix_web_middleware::DefaultHeaders::new().add(("x-powered-by", "actix-web")))
-            .wrap(rate_limiter_middleware())
+            .wrap(actix_web_middleware::from_fn(rate_limit_middleware))
             .validator_error_handler(Arc::new(validator_error_handler))
             .app_data(web::JsonConfig::default().error_handler(json_error_handler))
             .app_data(app_data.clone())
             .configure(home_controller::config)
             .configure(auth_controller::config)
             .configure(todo_controller::config)
+            .configure(tag_controller::config)
+            .configure(list_controller::config)
+            .configure(admin_controller::config)
             .default_service(web::route().to(not_found_controller::not_found_handler))
     })
     .workers(2)
@@ -4,8 +4,10 @@ use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
     http,
     middleware::Next,
+    web::Data,
 };
 
+use crate::app_state::AppState;
 use crate::models::errors::Error;
 use crate::utils::jwt::{AuthenticatedUser, verify_token};
 
@@ -25,6 +27,19 @@ pub async fn auth_middleware(
 
     let claims = verify_token(jwt_token).map_err(|_| Error::Unauthorized)?;
 
+    // Reject tokens that were logged out before they expired
+    let app_state = req
+        .app_data::<Data<AppState>>()
+        .ok_or_else(|| Error::InternalServerError("AppState is not configured".to_string()))?;
+
+    if app_state
+        .auth_service
+        .is_access_token_revoked(&claims)
+        .await?
+    {
+        return Err(Error::Unauthorized.into());
+    }
+
     req.extensions_mut()
         .insert(AuthenticatedUser(claims.clone()));
 
@@ -1,23 +1,224 @@
-use crate::models::errors::{Error, ErrorToHttp};
-use crate::utils::common::get_client_ip;
-use actix_web_ratelimit::{RateLimit, config::RateLimitConfig, store::MemoryStore};
+use actix_web::{
+    Error as ActixWebError,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        Method,
+        header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    },
+    middleware::Next,
+    web::Data,
+};
 use once_cell::sync::Lazy;
-use std::sync::Arc;
 
+use crate::app_state::AppState;
 use crate::config;
+use crate::models::errors::{Error, ErrorToHttp};
+use crate::services::rate_limit_service::RateLimitStatus;
+use crate::utils::common::get_client_ip;
+use crate::utils::jwt::token_subject;
 
-static STORE: Lazy<Arc<MemoryStore>> = Lazy::new(|| Arc::new(MemoryStore::new()));
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RateLimitKey {
+    Ip,
+    // Signed-in users get their own budget, anonymous requests are counted by IP
+    User,
+}
+
+#[derive(Debug)]
+struct RateLimitPolicy {
+    name: &'static str,
+    method: Option<Method>,
+    // Also matches everything below it
+    path: &'static str,
+    max_requests: u64,
+    window_secs: u64,
+    key: RateLimitKey,
+}
 
-static CONFIG: Lazy<RateLimitConfig> = Lazy::new(|| {
-    // Configure rate limiting: allow 3 requests per 10-second window
-    RateLimitConfig::default()
-        .max_requests(*config::RATE_LIMIT_MAX_REQUESTS)
-        .window_secs(*config::RATE_LIMIT_WINDOW_SECS)
-        .id(|req| get_client_ip(req))
-        // exceeded(|id, config, _req| {})
-        .exceeded(|_, _, _| Error::TooManyRequests.to_http_response())
+impl RateLimitPolicy {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method)
+            && path
+                .strip_prefix(self.path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+// First match wins, requests matching none of them get DEFAULT_POLICY
+static POLICIES: [RateLimitPolicy; 8] = [
+    RateLimitPolicy {
+        name: "signin",
+        method: Some(Method::POST),
+        path: "/auth/signin",
+        max_requests: 5,
+        window_secs: 60,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "signup",
+        method: Some(Method::POST),
+        path: "/auth/signup",
+        max_requests: 10,
+        window_secs: 3600,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "refresh-token",
+        method: Some(Method::POST),
+        path: "/auth/refresh-token",
+        max_requests: 30,
+        window_secs: 60,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "verify-email",
+        method: Some(Method::POST),
+        path: "/auth/verify-email",
+        max_requests: 10,
+        window_secs: 60,
+        key: RateLimitKey::Ip,
+    },
+    // Every request may send a mail
+    RateLimitPolicy {
+        name: "resend-verification",
+        method: Some(Method::POST),
+        path: "/auth/resend-verification",
+        max_requests: 3,
+        window_secs: 3600,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "forgot-password",
+        method: Some(Method::POST),
+        path: "/auth/forgot-password",
+        max_requests: 3,
+        window_secs: 3600,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "reset-password",
+        method: Some(Method::POST),
+        path: "/auth/reset-password",
+        max_requests: 10,
+        window_secs: 60,
+        key: RateLimitKey::Ip,
+    },
+    RateLimitPolicy {
+        name: "todos-read",
+        method: Some(Method::GET),
+        path: "/todos",
+        max_requests: 300,
+        window_secs: 60,
+        key: RateLimitKey::User,
+    },
+];
+
+static DEFAULT_POLICY: Lazy<RateLimitPolicy> = Lazy::new(|| RateLimitPolicy {
+    name: "default",
+    method: None,
+    path: "/",
+    max_requests: *config::RATE_LIMIT_MAX_REQUESTS as u64,
+    window_secs: *config::RATE_LIMIT_WINDOW_SECS,
+    key: RateLimitKey::User,
 });
 
-pub fn rate_limiter_middleware() -> RateLimit<Arc<MemoryStore>> {
-    RateLimit::new(CONFIG.clone(), STORE.clone())
+fn find_policy(method: &Method, path: &str) -> &'static RateLimitPolicy {
+    POLICIES
+        .iter()
+        .find(|policy| policy.matches(method, path))
+        .unwrap_or(&DEFAULT_POLICY)
+}
+
+// The token is only decoded here, auth_middleware still rejects revoked ones
+fn client_key(req: &ServiceRequest, key: RateLimitKey) -> String {
+    let user_id = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .and_then(token_subject);
+
+    match user_id {
+        Some(user_id) if key == RateLimitKey::User => format!("user:{}", user_id),
+        _ => format!("ip:{}", get_client_ip(req.request())),
+    }
+}
+
+// RateLimit-* headers of the IETF draft (draft-ietf-httpapi-ratelimit-headers)
+fn insert_headers(headers: &mut HeaderMap, policy: &RateLimitPolicy, status: &RateLimitStatus) {
+    let values = [
+        ("ratelimit-limit", status.limit.to_string()),
+        ("ratelimit-remaining", status.remaining.to_string()),
+        ("ratelimit-reset", status.reset_secs.to_string()),
+        (
+            "ratelimit-policy",
+            format!("{};w={}", policy.max_requests, policy.window_secs),
+        ),
+    ];
+
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
+
+pub async fn rate_limit_middleware<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<impl MessageBody>, ActixWebError> {
+    let rate_limit_service = req
+        .app_data::<Data<AppState>>()
+        .map(|app_state| app_state.rate_limit_service.clone())
+        .ok_or_else(|| Error::InternalServerError("AppState is not configured".to_string()))?;
+
+    let policy = find_policy(req.method(), req.path());
+    let key = format!("{}:{}", policy.name, client_key(&req, policy.key));
+
+    let status = match rate_limit_service
+        .hit(&key, policy.max_requests, policy.window_secs)
+        .await
+    {
+        Ok(status) => status,
+        // Redis being unavailable shouldn't take the whole API down with it
+        Err(e) => {
+            log::error!("rate limit check failed for {}: {:?}", key, e);
+            let res = next.call(req).await?;
+            return Ok(res.map_into_left_body());
+        }
+    };
+
+    if !status.allowed {
+        log::warn!("rate limit exceeded -> {}", key);
+
+        let mut response = Error::TooManyRequests.to_http_response();
+        insert_headers(response.headers_mut(), policy, &status);
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(status.reset_secs));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+
+    let mut res = next.call(req).await?;
+    insert_headers(res.headers_mut(), policy, &status);
+    Ok(res.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_policy_by_method_and_path() {
+        assert_eq!(find_policy(&Method::POST, "/auth/signin").name, "signin");
+        assert_eq!(find_policy(&Method::GET, "/todos").name, "todos-read");
+        assert_eq!(
+            find_policy(&Method::GET, "/todos/123/items").name,
+            "todos-read"
+        );
+        assert_eq!(find_policy(&Method::POST, "/todos").name, "default");
+        assert_eq!(find_policy(&Method::GET, "/todos-archive").name, "default");
+        assert_eq!(find_policy(&Method::GET, "/auth/signin").name, "default");
+    }
 }
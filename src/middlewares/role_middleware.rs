@@ -0,0 +1,116 @@
+use actix_web::{
+    Error as ActixWebError, HttpMessage,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+};
+use futures::future::LocalBoxFuture;
+
+use crate::models::errors::Error;
+use crate::utils::jwt::{AuthenticatedUser, JwtClaims};
+
+// Must be wrapped inside auth_middleware, e.g.
+// scope("/admin").wrap(from_fn(require_role("admin"))).wrap(from_fn(auth_middleware))
+pub fn require_role<B>(
+    role: &'static str,
+) -> impl Fn(ServiceRequest, Next<B>) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, ActixWebError>>
+where
+    B: MessageBody + 'static,
+{
+    move |req, next| {
+        Box::pin(check_claims(
+            req,
+            next,
+            move |claims| claims.has_role(role),
+            role,
+        ))
+    }
+}
+
+// Same as require_role for a single route, e.g. #[get("/users", wrap = "from_fn(require_permission(\"users:read\"))")]
+pub fn require_permission<B>(
+    permission: &'static str,
+) -> impl Fn(ServiceRequest, Next<B>) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, ActixWebError>>
+where
+    B: MessageBody + 'static,
+{
+    move |req, next| {
+        Box::pin(check_claims(
+            req,
+            next,
+            move |claims| claims.has_permission(permission),
+            permission,
+        ))
+    }
+}
+
+async fn check_claims<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+    is_allowed: impl Fn(&JwtClaims) -> bool,
+    requirement: &'static str,
+) -> Result<ServiceResponse<B>, ActixWebError> {
+    let allowed = req
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|user| is_allowed(user))
+        .ok_or_else(|| Error::Unauthorized)?;
+
+    if !allowed {
+        log::warn!("check_claims -> missing: {}", requirement);
+        return Err(Error::Forbidden.into());
+    }
+
+    next.call(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        App, HttpResponse, dev::Service, http::StatusCode, middleware::from_fn, test, web,
+    };
+    use uuid::Uuid;
+
+    async fn status_with(claims: JwtClaims) -> StatusCode {
+        let app = test::init_service(
+            App::new().service(
+                web::resource("/admin/users")
+                    .wrap(from_fn(require_permission("users:read")))
+                    .wrap_fn(move |req, srv| {
+                        req.extensions_mut()
+                            .insert(AuthenticatedUser(claims.clone()));
+                        srv.call(req)
+                    })
+                    .to(HttpResponse::Ok),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/admin/users").to_request();
+        match test::try_call_service(&app, req).await {
+            Ok(res) => res.status(),
+            Err(e) => e.error_response().status(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_require_permission_checks_the_permissions_of_the_token() {
+        let claims = |permissions: &[&str]| {
+            JwtClaims::new(
+                Uuid::new_v4(),
+                "admin@example.com".to_string(),
+                "Admin".to_string(),
+                vec!["admin".to_string()],
+                permissions.iter().map(|p| p.to_string()).collect(),
+            )
+        };
+
+        assert_eq!(status_with(claims(&["users:read"])).await, StatusCode::OK);
+        // The role alone isn't enough
+        assert_eq!(
+            status_with(claims(&["users:disable"])).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -1,5 +1,9 @@
-use chrono::{self, DateTime, Utc, serde::ts_milliseconds};
-use entity::t_users;
+use chrono::{
+    self, DateTime, Utc,
+    serde::{ts_milliseconds, ts_milliseconds_option},
+};
+use entity::{t_todos, t_users};
+use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -26,6 +30,8 @@ pub struct User {
         with = "ts_milliseconds"
     )]
     pub updated_at: DateTime<Utc>,
+    #[serde(rename = "emailVerifiedAt", default, with = "ts_milliseconds_option")]
+    pub email_verified_at: Option<DateTime<Utc>>,
 }
 
 // Cho phép gọi .into() trên t_users::Model để convert sang User
@@ -37,6 +43,17 @@ impl From<t_users::Model> for User {
             email: user.email,
             created_at: user.created_at.with_timezone(&Utc),
             updated_at: user.updated_at.with_timezone(&Utc),
+            email_verified_at: user.email_verified_at.map(|at| at.with_timezone(&Utc)),
         }
     }
 }
+
+// Row of a full-text search over t_todos
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct TodoSearchRow {
+    #[sea_orm(nested)]
+    pub todo: t_todos::Model,
+    pub rank: f32,
+    pub title_highlight: String,
+    pub description_highlight: String,
+}
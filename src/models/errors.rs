@@ -1,7 +1,8 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{HttpResponse, ResponseError, http::header};
 use serde_json::json;
 use tokio_cron_scheduler::JobSchedulerError;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     // Dùng cho lỗi DB
@@ -24,9 +25,27 @@ pub enum Error {
     #[error("{0}")]
     UnauthorizedWithMessage(String),
 
+    #[error("Forbidden")]
+    Forbidden,
+
+    #[error("{0}")]
+    ForbiddenWithMessage(String),
+
     #[error("Bad Request: {0}")]
     BadRequest(String),
 
+    #[error("Not Found: {0}")]
+    NotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Precondition Failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Unsupported Media Type: {0}")]
+    UnsupportedMediaType(String),
+
     #[error("Internal Server Error: {0}")]
     InternalServerError(String),
 
@@ -38,6 +57,9 @@ pub enum Error {
 
     #[error("Too Many Requests")]
     TooManyRequests,
+
+    #[error("{message}")]
+    TooManyRequestsWithMessage { message: String, retry_after: u64 },
 }
 
 // Dùng cho auth_mw
@@ -65,16 +87,58 @@ impl ErrorToHttp for Error {
                 "message": message,
             })),
 
+            Error::Forbidden => HttpResponse::Forbidden().json(json!({
+                "statusCode": 403,
+                "message": "Forbidden",
+            })),
+
+            Error::ForbiddenWithMessage(message) => HttpResponse::Forbidden().json(json!({
+                "statusCode": 403,
+                "message": message,
+            })),
+
             Error::BadRequest(message) => HttpResponse::BadRequest().json(json!({
                 "statusCode": 400,
                 "message": message,
             })),
 
+            Error::NotFound(message) => HttpResponse::NotFound().json(json!({
+                "statusCode": 404,
+                "message": message,
+            })),
+
+            Error::Conflict(message) => HttpResponse::Conflict().json(json!({
+                "statusCode": 409,
+                "message": message,
+            })),
+
+            Error::PreconditionFailed(message) => HttpResponse::PreconditionFailed().json(json!({
+                "statusCode": 412,
+                "message": message,
+            })),
+
+            Error::UnsupportedMediaType(message) => {
+                HttpResponse::UnsupportedMediaType().json(json!({
+                    "statusCode": 415,
+                    "message": message,
+                }))
+            }
+
             Error::TooManyRequests => HttpResponse::TooManyRequests().json(json!({
                 "statusCode": 429,
                 "message": "Too Many Requests",
             })),
 
+            Error::TooManyRequestsWithMessage {
+                message,
+                retry_after,
+            } => HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .json(json!({
+                    "statusCode": 429,
+                    "message": message,
+                })),
+
             _ => {
                 log::error!("Internal server error: {:?}", self);
                 HttpResponse::InternalServerError().json(json!({
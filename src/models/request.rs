@@ -1,6 +1,16 @@
-use crate::{utils::hash::hash_password, validators::validate_password};
-use chrono::Utc;
-use entity::*;
+use crate::{
+    utils::{hash::hash_password, merge_patch::deserialize_nullable},
+    validators::{
+        validate_create_todo, validate_not_blank, validate_password, validate_recurrence,
+        validate_tag_color, validate_todo_filters, validate_todo_patch, validate_todo_sort,
+        validate_update_todo, validate_user_patch,
+    },
+};
+use chrono::{DateTime, Utc};
+use entity::{
+    sea_orm_active_enums::{ListRole, TodoPriority},
+    *,
+};
 use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -40,6 +50,33 @@ pub struct SignInRequest {
     pub password: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct ResendVerificationRequest {
+    #[validate(email(message = "Email must be valid email address"))]
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Email must be valid email address"))]
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, message = "Token is required"))]
+    pub token: String,
+
+    #[validate(custom(function = validate_password))]
+    pub password: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Validate)]
 pub struct UpdateUserRequest {
     #[validate(length(min = 3, message = "Name must be at least 3 characters long"))]
@@ -49,6 +86,28 @@ pub struct UpdateUserRequest {
     pub password: Option<String>,
 }
 
+// Body of PATCH /auth/me (application/merge-patch+json)
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+#[validate(schema(function = validate_user_patch))]
+pub struct PatchUserRequest {
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate(length(min = 3, message = "Name must be at least 3 characters long"))]
+    pub name: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate(custom(function = validate_password))]
+    pub password: Option<Option<String>>,
+}
+
+impl From<PatchUserRequest> for UpdateUserRequest {
+    fn from(patch: PatchUserRequest) -> Self {
+        Self {
+            name: patch.name.flatten(),
+            password: patch.password.flatten(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct RefreshTokenRequest {
@@ -57,8 +116,9 @@ pub struct RefreshTokenRequest {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+#[validate(schema(function = validate_todo_filters))]
 pub struct GetAllTodosRequest {
-    #[validate(range(min = 0, message = "Page must be greater than or equal to 0"))]
+    #[validate(range(min = 1, message = "Page must be greater than 0"))]
     pub page: Option<u64>,
 
     #[validate(range(min = 1, message = "Page size must be greater than 0"))]
@@ -67,9 +127,204 @@ pub struct GetAllTodosRequest {
 
     #[validate(length(min = 3, message = "Search must be at least 3 characters long"))]
     pub search: Option<String>,
+
+    // Opaque `nextCursor` from a previous response, switches to keyset pagination
+    #[validate(length(min = 1, message = "Cursor must not be empty"))]
+    pub cursor: Option<String>,
+
+    #[validate(length(min = 3, message = "Description must be at least 3 characters long"))]
+    pub description: Option<String>,
+
+    pub completed: Option<bool>,
+
+    #[serde(rename = "createdFrom")]
+    pub created_from: Option<DateTime<Utc>>,
+
+    #[serde(rename = "createdTo")]
+    pub created_to: Option<DateTime<Utc>>,
+
+    #[serde(rename = "updatedFrom")]
+    pub updated_from: Option<DateTime<Utc>>,
+
+    #[serde(rename = "updatedTo")]
+    pub updated_to: Option<DateTime<Utc>>,
+
+    pub priority: Option<TodoPriority>,
+
+    #[serde(rename = "dueFrom")]
+    pub due_from: Option<DateTime<Utc>>,
+
+    #[serde(rename = "dueTo")]
+    pub due_to: Option<DateTime<Utc>>,
+
+    // Comma separated tag names, e.g. `work,urgent`
+    #[validate(length(min = 1, message = "Tag must not be empty"))]
+    pub tag: Option<String>,
+
+    // Whether todos need `any` (default) or `all` of the tags
+    #[serde(rename = "tagMatch")]
+    pub tag_match: Option<TagMatch>,
+
+    // Only the todos of this shared list
+    #[serde(rename = "listId")]
+    pub list_id: Option<Uuid>,
+
+    // `field[:asc|desc]`, e.g. `title:asc`
+    #[validate(custom(function = validate_todo_sort))]
+    pub sort: Option<String>,
+
+    // Full-text query in websearch syntax, e.g. `groceries -milk "olive oil"`
+    #[validate(length(min = 2, message = "Query must be at least 2 characters long"))]
+    pub q: Option<String>,
+}
+
+impl GetAllTodosRequest {
+    pub fn filters(&self) -> TodoFilters {
+        TodoFilters {
+            search: self.search.clone(),
+            text_query: self.q.clone(),
+            description: self.description.clone(),
+            completed: self.completed,
+            created_from: self.created_from,
+            created_to: self.created_to,
+            updated_from: self.updated_from,
+            updated_to: self.updated_to,
+            priority: self.priority,
+            due_from: self.due_from,
+            due_to: self.due_to,
+            tags: self
+                .tag
+                .as_deref()
+                .map(|tags| {
+                    let mut tags: Vec<String> = tags
+                        .split(',')
+                        .map(|tag| tag.trim().to_lowercase())
+                        .filter(|tag| !tag.is_empty())
+                        .collect();
+                    tags.sort();
+                    tags.dedup();
+                    tags
+                })
+                .unwrap_or_default(),
+            tag_match: self.tag_match.unwrap_or_default(),
+            list_id: self.list_id,
+            sort: self
+                .sort
+                .as_deref()
+                .and_then(TodoSort::parse)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoSortField {
+    CreatedAt,
+    UpdatedAt,
+    Title,
+    Completed,
+    DueAt,
+    Priority,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TodoSort {
+    pub field: TodoSortField,
+    pub direction: SortDirection,
+}
+
+impl Default for TodoSort {
+    fn default() -> Self {
+        Self {
+            field: TodoSortField::CreatedAt,
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+impl TodoSort {
+    // Only whitelisted columns can be sorted on, direction defaults to desc
+    pub fn parse(sort: &str) -> Option<Self> {
+        let (field, direction) = sort.split_once(':').unwrap_or((sort, "desc"));
+
+        let field = match field {
+            "createdAt" => TodoSortField::CreatedAt,
+            "updatedAt" => TodoSortField::UpdatedAt,
+            "title" => TodoSortField::Title,
+            "completed" => TodoSortField::Completed,
+            "dueAt" => TodoSortField::DueAt,
+            "priority" => TodoSortField::Priority,
+            _ => return None,
+        };
+
+        let direction = match direction.to_ascii_lowercase().as_str() {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            _ => return None,
+        };
+
+        Some(Self { field, direction })
+    }
+}
+
+// Filters of GET /todos, translated to SQL by TodoRepository
+#[derive(Clone, Debug, Default)]
+pub struct TodoFilters {
+    pub search: Option<String>,
+    // Full-text search, results are ranked before the requested sort applies
+    pub text_query: Option<String>,
+    pub description: Option<String>,
+    pub completed: Option<bool>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    pub updated_from: Option<DateTime<Utc>>,
+    pub updated_to: Option<DateTime<Utc>>,
+    pub priority: Option<TodoPriority>,
+    pub due_from: Option<DateTime<Utc>>,
+    pub due_to: Option<DateTime<Utc>>,
+    // Lowercased and deduplicated tag names
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    pub list_id: Option<Uuid>,
+    pub sort: TodoSort,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct GetTrashTodosRequest {
+    #[validate(range(min = 1, message = "Page must be greater than 0"))]
+    pub page: Option<u64>,
+
+    #[validate(range(min = 1, message = "Page size must be greater than 0"))]
+    #[serde(rename = "pageSize")]
+    pub page_size: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct GetAllUsersRequest {
+    #[validate(range(min = 1, message = "Page must be greater than 0"))]
+    pub page: Option<u64>,
+
+    #[validate(range(min = 1, message = "Page size must be greater than 0"))]
+    #[serde(rename = "pageSize")]
+    pub page_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+#[validate(schema(function = validate_create_todo))]
 pub struct CreateTodoRequest {
     #[validate(length(min = 3, message = "Title must be at least 3 characters long"))]
     pub title: String,
@@ -78,6 +333,22 @@ pub struct CreateTodoRequest {
     pub description: String,
 
     pub completed: Option<bool>,
+
+    #[serde(rename = "dueAt")]
+    pub due_at: Option<DateTime<Utc>>,
+
+    pub priority: Option<TodoPriority>,
+
+    #[serde(rename = "remindAt")]
+    pub remind_at: Option<DateTime<Utc>>,
+
+    // Cron expression or RRULE, the next occurrence is created when the todo is completed
+    #[validate(custom(function = validate_recurrence))]
+    pub recurrence: Option<String>,
+
+    // Creates the todo in a shared list instead of the user's personal todos
+    #[serde(rename = "listId")]
+    pub list_id: Option<Uuid>,
 }
 
 impl CreateTodoRequest {
@@ -87,21 +358,311 @@ impl CreateTodoRequest {
             title: Set(self.title),
             description: Set(self.description),
             completed: Set(self.completed.unwrap_or(false)),
-            created_at: Set(Utc::now().into()),
-            updated_at: Set(Utc::now().into()),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
             user_id: Set(user_id),
+            due_at: Set(self.due_at),
+            priority: Set(self.priority.unwrap_or_default()),
+            remind_at: Set(self.remind_at),
+            recurrence: Set(self.recurrence),
+            list_id: Set(self.list_id),
             ..Default::default()
         }
     }
 }
 
+// Body of PUT /todos/{id}, replaces the whole todo
 #[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+#[validate(schema(function = validate_update_todo))]
 pub struct UpdateTodoRequest {
     #[validate(length(min = 3, message = "Title must be at least 3 characters long"))]
-    pub title: Option<String>,
+    pub title: String,
 
     #[validate(length(min = 3, message = "Description must be at least 3 characters long"))]
-    pub description: Option<String>,
+    pub description: String,
+
+    pub completed: bool,
+
+    #[serde(rename = "dueAt")]
+    pub due_at: Option<DateTime<Utc>>,
+
+    pub priority: Option<TodoPriority>,
+
+    #[serde(rename = "remindAt")]
+    pub remind_at: Option<DateTime<Utc>>,
+
+    // Cron expression or RRULE, the next occurrence is created when the todo is completed
+    #[validate(custom(function = validate_recurrence))]
+    pub recurrence: Option<String>,
+}
+
+impl UpdateTodoRequest {
+    pub fn into_active_model(self) -> t_todos::ActiveModel {
+        t_todos::ActiveModel {
+            title: Set(self.title),
+            description: Set(self.description),
+            completed: Set(self.completed),
+            due_at: Set(self.due_at),
+            priority: Set(self.priority.unwrap_or_default()),
+            remind_at: Set(self.remind_at),
+            recurrence: Set(self.recurrence),
+            ..Default::default()
+        }
+    }
+}
+
+// Body of PATCH /todos/{id} (application/merge-patch+json), absent fields are left untouched
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+#[validate(schema(function = validate_todo_patch))]
+pub struct PatchTodoRequest {
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate(length(min = 3, message = "Title must be at least 3 characters long"))]
+    pub title: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate(length(min = 3, message = "Description must be at least 3 characters long"))]
+    pub description: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub completed: Option<Option<bool>>,
+
+    // null clears the due date or the reminder
+    #[serde(rename = "dueAt", default, deserialize_with = "deserialize_nullable")]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub priority: Option<Option<TodoPriority>>,
+
+    #[serde(
+        rename = "remindAt",
+        default,
+        deserialize_with = "deserialize_nullable"
+    )]
+    pub remind_at: Option<Option<DateTime<Utc>>>,
+
+    // null stops the series
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate(custom(function = validate_recurrence))]
+    pub recurrence: Option<Option<String>>,
+}
+
+impl PatchTodoRequest {
+    // Nulls of required fields are rejected by validate_todo_patch, only present values are set
+    pub fn into_active_model(self) -> t_todos::ActiveModel {
+        let mut todo = t_todos::ActiveModel::default();
+
+        if let Some(Some(title)) = self.title {
+            todo.title = Set(title);
+        }
+
+        if let Some(Some(description)) = self.description {
+            todo.description = Set(description);
+        }
+
+        if let Some(Some(completed)) = self.completed {
+            todo.completed = Set(completed);
+        }
+
+        if let Some(due_at) = self.due_at {
+            todo.due_at = Set(due_at);
+        }
+
+        if let Some(Some(priority)) = self.priority {
+            todo.priority = Set(priority);
+        }
+
+        if let Some(remind_at) = self.remind_at {
+            todo.remind_at = Set(remind_at);
+        }
+
+        if let Some(recurrence) = self.recurrence {
+            todo.recurrence = Set(recurrence);
+        }
+
+        todo
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct BatchTodosRequest {
+    // All-or-nothing: the first failed operation rolls the whole batch back
+    #[serde(default)]
+    pub atomic: bool,
+
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Operations must contain between 1 and 100 items"
+    ))]
+    pub operations: Vec<BatchTodoOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchTodoOperation {
+    Create {
+        data: CreateTodoRequest,
+    },
+    // `version` works like If-Match for a single todo
+    Update {
+        id: Uuid,
+        version: Option<i32>,
+        data: PatchTodoRequest,
+    },
+    Delete {
+        id: Uuid,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct CreateTagRequest {
+    #[validate(length(
+        min = 1,
+        max = 50,
+        message = "Name must be between 1 and 50 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Name must not be blank"))]
+    pub name: String,
+
+    // `#RRGGBB`
+    #[validate(custom(function = validate_tag_color))]
+    pub color: Option<String>,
+}
+
+impl CreateTagRequest {
+    pub fn into_active_model(self, user_id: Uuid) -> t_tags::ActiveModel {
+        t_tags::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            name: Set(self.name.trim().to_string()),
+            color: Set(self.color),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct UpdateTagRequest {
+    #[validate(length(
+        min = 1,
+        max = 50,
+        message = "Name must be between 1 and 50 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Name must not be blank"))]
+    pub name: Option<String>,
+
+    #[validate(custom(function = validate_tag_color))]
+    pub color: Option<String>,
+}
+
+// Body of PUT /todos/{id}/tags, replaces all tags of the todo
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct SetTodoTagsRequest {
+    #[serde(rename = "tagIds")]
+    #[validate(length(max = 50, message = "A todo can have at most 50 tags"))]
+    pub tag_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct CreateTodoItemRequest {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Title must be between 1 and 200 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Title must not be blank"))]
+    pub title: String,
 
     pub completed: Option<bool>,
 }
+
+impl CreateTodoItemRequest {
+    // todo_id and position are set by the repository
+    pub fn into_active_model(self) -> t_todo_items::ActiveModel {
+        t_todo_items::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            title: Set(self.title.trim().to_string()),
+            completed: Set(self.completed.unwrap_or(false)),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct UpdateTodoItemRequest {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Title must be between 1 and 200 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Title must not be blank"))]
+    pub title: Option<String>,
+
+    pub completed: Option<bool>,
+}
+
+// Body of PUT /todos/{id}/items/order, lists every item of the todo in its new order
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct ReorderTodoItemsRequest {
+    #[serde(rename = "itemIds")]
+    pub item_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct CreateListRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Name must not be blank"))]
+    pub name: String,
+}
+
+impl CreateListRequest {
+    pub fn into_active_model(self) -> t_lists::ActiveModel {
+        t_lists::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(self.name.trim().to_string()),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct UpdateListRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters long"
+    ))]
+    #[validate(custom(function = validate_not_blank, message = "Name must not be blank"))]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct UpdateListMemberRequest {
+    pub role: ListRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct InviteListMemberRequest {
+    #[validate(email(message = "Email must be valid email address"))]
+    pub email: String,
+
+    pub role: ListRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Validate)]
+pub struct GetAuditLogRequest {
+    #[validate(range(min = 1, message = "Page must be greater than 0"))]
+    pub page: Option<u64>,
+
+    #[validate(range(min = 1, message = "Page size must be greater than 0"))]
+    #[serde(rename = "pageSize")]
+    pub page_size: Option<u64>,
+}
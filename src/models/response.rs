@@ -1,7 +1,11 @@
-use entity::t_todos;
+use chrono::{DateTime, Utc};
+use entity::{
+    sea_orm_active_enums::ListRole, t_audit_log, t_list_invitations, t_list_members, t_lists,
+    t_todos,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::models::db::User;
+use crate::models::db::{TodoSearchRow, User};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CommonResponse<T> {
@@ -10,10 +14,11 @@ pub struct CommonResponse<T> {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SignUpResponse {
-    #[serde(rename = "accessToken")]
-    pub access_token: String,
-    #[serde(rename = "refreshToken")]
-    pub refresh_token: String,
+    // Left out while the email address still has to be verified before signing in
+    #[serde(rename = "accessToken", skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(rename = "refreshToken", skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
     pub user: User,
 }
 
@@ -42,8 +47,164 @@ pub struct RefreshTokenResponse {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GetAllTodosResponse {
-    pub total: usize,
+    // page, total and totalPages are only returned in page mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    #[serde(rename = "pageSize")]
+    pub page_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(rename = "totalPages", skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u64>,
+    #[serde(rename = "hasNext")]
+    pub has_next: bool,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    pub data: Vec<TodoListItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TodoListItem {
+    #[serde(flatten)]
+    pub todo: t_todos::Model,
+    // Only set for full-text search results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<TodoHighlight>,
+    // Checklist progress, both 0 for todos without items
+    #[serde(rename = "itemCount")]
+    pub item_count: u64,
+    #[serde(rename = "completedItemCount")]
+    pub completed_item_count: u64,
+}
+
+impl From<t_todos::Model> for TodoListItem {
+    fn from(todo: t_todos::Model) -> Self {
+        Self {
+            todo,
+            highlight: None,
+            item_count: 0,
+            completed_item_count: 0,
+        }
+    }
+}
+
+impl From<TodoSearchRow> for TodoListItem {
+    fn from(row: TodoSearchRow) -> Self {
+        Self {
+            todo: row.todo,
+            highlight: Some(TodoHighlight {
+                title: row.title_highlight,
+                description: row.description_highlight,
+                rank: row.rank,
+            }),
+            item_count: 0,
+            completed_item_count: 0,
+        }
+    }
+}
+
+// Matched terms are wrapped in <mark></mark>
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TodoHighlight {
+    pub title: String,
+    pub description: String,
+    pub rank: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdminUser {
+    #[serde(flatten)]
+    pub user: User,
+    pub roles: Vec<String>,
+    #[serde(rename = "disabledAt")]
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RoleResponse {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetRolesResponse(pub Vec<RoleResponse>);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetAllUsersResponse {
+    pub total: u64,
+    #[serde(rename = "totalPages")]
+    pub total_pages: u64,
+    pub data: Vec<AdminUser>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchTodosResponse {
+    // false when an atomic batch was rolled back
+    pub committed: bool,
+    pub results: Vec<BatchTodoResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchTodoResult {
+    pub index: usize,
+    #[serde(rename = "statusCode")]
+    pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<t_todos::Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListResponse {
+    #[serde(flatten)]
+    pub list: t_lists::Model,
+    // Role of the current user in the list
+    pub role: ListRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListMemberResponse {
+    #[serde(flatten)]
+    pub member: t_list_members::Model,
+    pub name: String,
+    pub email: String,
+}
+
+// Invitation received by the current user
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReceivedInvitationResponse {
+    #[serde(flatten)]
+    pub invitation: t_list_invitations::Model,
+    #[serde(rename = "listName")]
+    pub list_name: String,
+}
+
+// Newest entries first
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GetAuditLogResponse {
+    pub page: u64,
+    #[serde(rename = "pageSize")]
+    pub page_size: u64,
+    pub total: u64,
     #[serde(rename = "totalPages")]
-    pub total_pages: usize,
-    pub data: Vec<t_todos::Model>,
+    pub total_pages: u64,
+    #[serde(rename = "hasNext")]
+    pub has_next: bool,
+    pub data: Vec<t_audit_log::Model>,
+}
+
+impl GetAuditLogResponse {
+    pub fn new(page: u64, page_size: u64, total: u64, data: Vec<t_audit_log::Model>) -> Self {
+        let total_pages = total.div_ceil(page_size);
+
+        Self {
+            page,
+            page_size,
+            total,
+            total_pages,
+            has_next: page < total_pages,
+            data,
+        }
+    }
 }
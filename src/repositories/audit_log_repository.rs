@@ -0,0 +1,99 @@
+use crate::models::errors::Error;
+use entity::{sea_orm_active_enums::AuditEntityType, t_audit_log};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Select,
+};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AuditLogRepository {
+    pub db: DatabaseConnection,
+}
+
+impl AuditLogRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    // Newest first, id breaks ties so the ordering is stable across pages
+    fn newest_first(query: Select<t_audit_log::Entity>) -> Select<t_audit_log::Entity> {
+        query
+            .order_by_desc(t_audit_log::Column::CreatedAt)
+            .order_by_desc(t_audit_log::Column::Id)
+    }
+
+    fn find_by_entity(
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+    ) -> Select<t_audit_log::Entity> {
+        Self::newest_first(
+            t_audit_log::Entity::find()
+                .filter(t_audit_log::Column::EntityType.eq(entity_type))
+                .filter(t_audit_log::Column::EntityId.eq(entity_id)),
+        )
+    }
+
+    fn find_by_actor(actor_id: Uuid) -> Select<t_audit_log::Entity> {
+        Self::newest_first(
+            t_audit_log::Entity::find().filter(t_audit_log::Column::ActorId.eq(actor_id)),
+        )
+    }
+
+    pub async fn create(
+        &self,
+        entry: t_audit_log::ActiveModel,
+    ) -> Result<t_audit_log::Model, Error> {
+        let entry = entry.insert(&self.db).await?;
+        Ok(entry)
+    }
+
+    // Returns one page of entries (page is 0-based) and the total number of entries
+    pub async fn get_entity_history(
+        &self,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        limit: u64,
+        page: u64,
+    ) -> Result<(Vec<t_audit_log::Model>, u64), Error> {
+        let paginator = Self::find_by_entity(entity_type, entity_id).paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let entries = paginator.fetch_page(page).await?;
+        Ok((entries, total))
+    }
+
+    pub async fn get_actor_activity(
+        &self,
+        actor_id: Uuid,
+        limit: u64,
+        page: u64,
+    ) -> Result<(Vec<t_audit_log::Model>, u64), Error> {
+        let paginator = Self::find_by_actor(actor_id).paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let entries = paginator.fetch_page(page).await?;
+        Ok((entries, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    #[test]
+    fn test_find_by_entity_filters_on_type_and_id() {
+        let entity_id = Uuid::new_v4();
+
+        let sql = AuditLogRepository::find_by_entity(AuditEntityType::Todo, entity_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#""t_audit_log"."entity_type" = 'todo'"#));
+        assert!(sql.contains(&format!(r#""t_audit_log"."entity_id" = '{}'"#, entity_id)));
+        assert!(
+            sql.ends_with(r#"ORDER BY "t_audit_log"."created_at" DESC, "t_audit_log"."id" DESC"#)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use sea_orm::{
+    Database, DatabaseConnection, DbBackend, DbErr, EntityTrait, IdenStatic, Iterable, ModelTrait,
+    ProxyDatabaseTrait, ProxyExecResult, ProxyRow, Statement,
+};
+
+#[derive(Debug, Default)]
+struct State {
+    results: VecDeque<Vec<ProxyRow>>,
+    statements: Vec<Statement>,
+}
+
+// Stands in for Postgres in repository tests. Queries get the queued rows in order, and no rows
+// once the queue is empty, which is what Postgres answers when the filters match nothing.
+#[derive(Debug, Clone, Default)]
+pub struct FakeDatabase(Arc<Mutex<State>>);
+
+impl FakeDatabase {
+    pub async fn connect(&self) -> DatabaseConnection {
+        Database::connect_proxy(DbBackend::Postgres, Arc::new(Box::new(self.clone())))
+            .await
+            .unwrap()
+    }
+
+    // Rows returned by the next query
+    pub fn push_rows<M: ModelTrait>(&self, models: Vec<M>) -> &Self {
+        let rows = models
+            .into_iter()
+            .map(|model| {
+                <M::Entity as EntityTrait>::Column::iter()
+                    .map(|column| (column.as_str().to_string(), model.get(column)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into()
+            })
+            .collect();
+
+        self.0.lock().unwrap().results.push_back(rows);
+        self
+    }
+
+    pub fn statements(&self) -> Vec<Statement> {
+        self.0.lock().unwrap().statements.clone()
+    }
+}
+
+#[async_trait]
+impl ProxyDatabaseTrait for FakeDatabase {
+    async fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+        let mut state = self.0.lock().unwrap();
+        state.statements.push(statement);
+        Ok(state.results.pop_front().unwrap_or_default())
+    }
+
+    async fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+        self.0.lock().unwrap().statements.push(statement);
+        Ok(ProxyExecResult::default())
+    }
+}
@@ -0,0 +1,327 @@
+use crate::models::errors::Error;
+use entity::{
+    sea_orm_active_enums::ListRole, t_list_invitations, t_list_members, t_lists, t_users,
+};
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
+    sea_query::{Expr, Func, OnConflict, Query, SelectStatement},
+};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ListRepository {
+    pub db: DatabaseConnection,
+}
+
+impl ListRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    // Ids of the lists `user_id` is a member of with at least `role`, used by the todo
+    // queries to reach shared todos
+    pub fn member_list_ids(user_id: Uuid, role: ListRole) -> SelectStatement {
+        Query::select()
+            .column((t_list_members::Entity, t_list_members::Column::ListId))
+            .from(t_list_members::Entity)
+            .and_where(
+                Expr::col((t_list_members::Entity, t_list_members::Column::UserId)).eq(user_id),
+            )
+            .and_where(Expr::col((t_list_members::Entity, t_list_members::Column::Role)).gte(role))
+            .to_owned()
+    }
+
+    // Role of `user_id` in the list, None if they aren't a member or the list doesn't exist
+    pub async fn find_role<C: ConnectionTrait>(
+        conn: &C,
+        list_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<ListRole>, Error> {
+        let member = t_list_members::Entity::find_by_id((list_id, user_id))
+            .one(conn)
+            .await?;
+        Ok(member.map(|member| member.role))
+    }
+
+    pub async fn get_role(&self, list_id: Uuid, user_id: Uuid) -> Result<Option<ListRole>, Error> {
+        Self::find_role(&self.db, list_id, user_id).await
+    }
+
+    pub async fn get_lists(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<(t_lists::Model, t_list_members::Model)>, Error> {
+        let lists = t_list_members::Entity::find()
+            .find_also_related(t_lists::Entity)
+            .filter(t_list_members::Column::UserId.eq(user_id))
+            .order_by_asc(t_lists::Column::Name)
+            .order_by_asc(t_lists::Column::Id)
+            .all(&self.db)
+            .await?;
+
+        Ok(lists
+            .into_iter()
+            .filter_map(|(member, list)| list.map(|list| (list, member)))
+            .collect())
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<t_lists::Model>, Error> {
+        let list = t_lists::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(list)
+    }
+
+    // The creator becomes the first owner of the list
+    pub async fn create(
+        &self,
+        list: t_lists::ActiveModel,
+        owner_id: Uuid,
+    ) -> Result<t_lists::Model, Error> {
+        let txn = self.db.begin().await?;
+
+        let list = list.insert(&txn).await?;
+        t_list_members::ActiveModel {
+            list_id: Set(list.id),
+            user_id: Set(owner_id),
+            role: Set(ListRole::Owner),
+            created_at: Set(list.created_at),
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok(list)
+    }
+
+    pub async fn update(&self, list: t_lists::ActiveModel) -> Result<t_lists::Model, Error> {
+        let list = list.update(&self.db).await?;
+        Ok(list)
+    }
+
+    // Members and invitations go with the list, its todos go back to their creators
+    pub async fn delete(&self, id: Uuid) -> Result<u64, Error> {
+        let result = t_lists::Entity::delete_by_id(id).exec(&self.db).await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn get_members(
+        &self,
+        list_id: Uuid,
+    ) -> Result<Vec<(t_list_members::Model, t_users::Model)>, Error> {
+        let members = t_list_members::Entity::find()
+            .find_also_related(t_users::Entity)
+            .filter(t_list_members::Column::ListId.eq(list_id))
+            .order_by_desc(t_list_members::Column::Role)
+            .order_by_asc(t_list_members::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+
+        Ok(members
+            .into_iter()
+            .filter_map(|(member, user)| user.map(|user| (member, user)))
+            .collect())
+    }
+
+    // Changes the role of a member (Some) or removes them (None). Owners are locked first so
+    // two concurrent changes can't leave the list without an owner.
+    pub async fn change_member(
+        &self,
+        list_id: Uuid,
+        user_id: Uuid,
+        role: Option<ListRole>,
+    ) -> Result<Option<t_list_members::Model>, Error> {
+        let txn = self.db.begin().await?;
+
+        let owners = t_list_members::Entity::find()
+            .filter(t_list_members::Column::ListId.eq(list_id))
+            .filter(t_list_members::Column::Role.eq(ListRole::Owner))
+            .lock_exclusive()
+            .all(&txn)
+            .await?;
+
+        let member = t_list_members::Entity::find_by_id((list_id, user_id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Member {} not found", user_id)))?;
+
+        let is_last_owner = owners.len() == 1 && owners[0].user_id == user_id;
+        if is_last_owner && role != Some(ListRole::Owner) {
+            return Err(Error::Conflict(
+                "A list must keep at least one owner".to_string(),
+            ));
+        }
+
+        let member = match role {
+            Some(role) => {
+                let mut member: t_list_members::ActiveModel = member.into();
+                member.role = Set(role);
+                Some(member.update(&txn).await?)
+            }
+            None => {
+                t_list_members::Entity::delete_by_id((list_id, user_id))
+                    .exec(&txn)
+                    .await?;
+                None
+            }
+        };
+
+        txn.commit().await?;
+        Ok(member)
+    }
+
+    pub async fn create_invitation(
+        &self,
+        invitation: t_list_invitations::ActiveModel,
+    ) -> Result<t_list_invitations::Model, Error> {
+        let invitation = invitation.insert(&self.db).await?;
+        Ok(invitation)
+    }
+
+    pub async fn get_invitations(
+        &self,
+        list_id: Uuid,
+    ) -> Result<Vec<t_list_invitations::Model>, Error> {
+        let invitations = t_list_invitations::Entity::find()
+            .filter(t_list_invitations::Column::ListId.eq(list_id))
+            .order_by_desc(t_list_invitations::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(invitations)
+    }
+
+    // Pending invitations sent to `email`, ignoring case
+    pub async fn get_invitations_by_email(
+        &self,
+        email: &str,
+    ) -> Result<Vec<(t_list_invitations::Model, t_lists::Model)>, Error> {
+        let invitations = t_list_invitations::Entity::find()
+            .find_also_related(t_lists::Entity)
+            .filter(
+                Expr::expr(Func::lower(Expr::col((
+                    t_list_invitations::Entity,
+                    t_list_invitations::Column::Email,
+                ))))
+                .eq(email.to_lowercase()),
+            )
+            .order_by_desc(t_list_invitations::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+
+        Ok(invitations
+            .into_iter()
+            .filter_map(|(invitation, list)| list.map(|list| (invitation, list)))
+            .collect())
+    }
+
+    pub async fn find_invitation(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<t_list_invitations::Model>, Error> {
+        let invitation = t_list_invitations::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?;
+        Ok(invitation)
+    }
+
+    pub async fn delete_invitation(&self, id: Uuid) -> Result<u64, Error> {
+        let result = t_list_invitations::Entity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    // Turns the invitation into a membership, an existing membership keeps its role
+    pub async fn accept_invitation(
+        &self,
+        invitation: &t_list_invitations::Model,
+        user_id: Uuid,
+    ) -> Result<(), Error> {
+        let txn = self.db.begin().await?;
+
+        let deleted = t_list_invitations::Entity::delete_by_id(invitation.id)
+            .exec(&txn)
+            .await?;
+
+        // Accepted or declined concurrently
+        if deleted.rows_affected == 0 {
+            return Err(Error::NotFound(format!(
+                "Invitation with id {} not found",
+                invitation.id
+            )));
+        }
+
+        t_list_members::Entity::insert(t_list_members::ActiveModel {
+            list_id: Set(invitation.list_id),
+            user_id: Set(user_id),
+            role: Set(invitation.role),
+            created_at: Set(chrono::Utc::now()),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                t_list_members::Column::ListId,
+                t_list_members::Column::UserId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::fake_database::FakeDatabase;
+    use chrono::Utc;
+    use sea_orm::sea_query::PostgresQueryBuilder;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    #[test]
+    fn test_member_list_ids_requires_minimum_role() {
+        let user_id = Uuid::new_v4();
+
+        let sql = ListRepository::member_list_ids(user_id, ListRole::Editor)
+            .to_string(PostgresQueryBuilder);
+
+        assert!(sql.starts_with(r#"SELECT "t_list_members"."list_id" FROM "t_list_members""#));
+        assert!(sql.contains(&format!(r#""t_list_members"."user_id" = '{}'"#, user_id)));
+        assert!(sql.ends_with(r#""t_list_members"."role" >= 2"#));
+    }
+
+    #[test]
+    fn test_list_roles_are_ordered_by_access() {
+        assert!(ListRole::Viewer < ListRole::Editor);
+        assert!(ListRole::Editor < ListRole::Owner);
+
+        let sql = t_list_members::Entity::find()
+            .filter(t_list_members::Column::Role.eq(ListRole::Owner))
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.ends_with(r#""t_list_members"."role" = 3"#));
+    }
+
+    #[tokio::test]
+    async fn test_get_role_of_member_and_outsider() {
+        let (list_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let database = FakeDatabase::default();
+        let repository = ListRepository::new(database.connect().await);
+
+        database.push_rows(vec![t_list_members::Model {
+            list_id,
+            user_id,
+            role: ListRole::Editor,
+            created_at: Utc::now(),
+        }]);
+        let member = repository.get_role(list_id, user_id).await.unwrap();
+        assert_eq!(member, Some(ListRole::Editor));
+
+        let outsider = repository.get_role(list_id, Uuid::new_v4()).await.unwrap();
+        assert_eq!(outsider, None);
+    }
+}
@@ -1,3 +1,9 @@
+pub mod audit_log_repository;
+#[cfg(test)]
+pub mod fake_database;
+pub mod list_repository;
 pub mod refresh_token_repository;
+pub mod tag_repository;
+pub mod todo_item_repository;
 pub mod todo_repository;
 pub mod user_repository;
\ No newline at end of file
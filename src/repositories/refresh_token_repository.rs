@@ -1,7 +1,10 @@
 use crate::models::errors::Error;
 use chrono::Utc;
 use entity::t_refresh_token;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait,
+    sea_query::Expr,
+};
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -22,15 +25,65 @@ impl RefreshTokenRepository {
         Ok(refresh_token)
     }
 
-    pub async fn get_refresh_token(
+    // Find a token regardless of its expiry or revocation, used to detect reuse
+    pub async fn find_by_token(
         &self,
         token: Uuid,
     ) -> Result<Option<t_refresh_token::Model>, Error> {
         let refresh_token = t_refresh_token::Entity::find()
             .filter(t_refresh_token::Column::Token.eq(token))
-            .filter(t_refresh_token::Column::ExpiredAt.gt(Utc::now()))
             .one(&self.db)
             .await?;
         Ok(refresh_token)
     }
+
+    // Revoke `current_id` and insert its successor atomically.
+    // Returns None if `current_id` was already revoked (lost a concurrent rotation).
+    pub async fn rotate_refresh_token(
+        &self,
+        current_id: i32,
+        next: t_refresh_token::ActiveModel,
+    ) -> Result<Option<t_refresh_token::Model>, Error> {
+        let rotated = self
+            .db
+            .transaction::<_, Option<t_refresh_token::Model>, sea_orm::DbErr>(|txn| {
+                Box::pin(async move {
+                    let result = t_refresh_token::Entity::update_many()
+                        .col_expr(t_refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+                        .filter(t_refresh_token::Column::Id.eq(current_id))
+                        .filter(t_refresh_token::Column::RevokedAt.is_null())
+                        .exec(txn)
+                        .await?;
+
+                    if result.rows_affected == 0 {
+                        return Ok(None);
+                    }
+
+                    let next = next.insert(txn).await?;
+                    Ok(Some(next))
+                })
+            })
+            .await?;
+        Ok(rotated)
+    }
+
+    pub async fn revoke_family(&self, family_id: Uuid) -> Result<u64, Error> {
+        let result = t_refresh_token::Entity::update_many()
+            .col_expr(t_refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(t_refresh_token::Column::FamilyId.eq(family_id))
+            .filter(t_refresh_token::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn revoke_by_user(&self, user_id: Uuid) -> Result<u64, Error> {
+        let result = t_refresh_token::Entity::update_many()
+            .col_expr(t_refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(t_refresh_token::Column::UserId.eq(user_id))
+            .filter(t_refresh_token::Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
 }
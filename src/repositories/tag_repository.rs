@@ -0,0 +1,187 @@
+use crate::models::errors::Error;
+use entity::{t_tags, t_todo_tags};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select, TransactionTrait,
+    sea_query::Query,
+};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct TagRepository {
+    pub db: DatabaseConnection,
+}
+
+impl TagRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    // Like todos, tags are only ever reached through their owner
+    fn find_owned(user_id: Uuid) -> Select<t_tags::Entity> {
+        t_tags::Entity::find()
+            .filter(t_tags::Column::UserId.eq(user_id))
+            .order_by_asc(t_tags::Column::Name)
+            .order_by_asc(t_tags::Column::Id)
+    }
+
+    // Todos of a shared list carry the tags of every member, each only sees their own
+    fn find_by_todo(todo_id: Uuid, user_id: Uuid) -> Select<t_tags::Entity> {
+        Self::find_owned(user_id)
+            .join(JoinType::InnerJoin, t_tags::Relation::TTodoTags.def())
+            .filter(t_todo_tags::Column::TodoId.eq(todo_id))
+    }
+
+    pub async fn get_all_tags(&self, user_id: Uuid) -> Result<Vec<t_tags::Model>, Error> {
+        let tags = Self::find_owned(user_id).all(&self.db).await?;
+        Ok(tags)
+    }
+
+    pub async fn find_by_id(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<t_tags::Model>, Error> {
+        let tag = Self::find_owned(user_id)
+            .filter(t_tags::Column::Id.eq(id))
+            .one(&self.db)
+            .await?;
+        Ok(tag)
+    }
+
+    pub async fn find_by_ids(
+        &self,
+        ids: &[Uuid],
+        user_id: Uuid,
+    ) -> Result<Vec<t_tags::Model>, Error> {
+        let tags = Self::find_owned(user_id)
+            .filter(t_tags::Column::Id.is_in(ids.iter().copied()))
+            .all(&self.db)
+            .await?;
+        Ok(tags)
+    }
+
+    pub async fn create(&self, tag: t_tags::ActiveModel) -> Result<t_tags::Model, Error> {
+        let tag = tag.insert(&self.db).await?;
+        Ok(tag)
+    }
+
+    pub async fn update(&self, tag: t_tags::ActiveModel) -> Result<t_tags::Model, Error> {
+        let tag = tag.update(&self.db).await?;
+        Ok(tag)
+    }
+
+    // Returns the number of deleted rows, the tag is removed from its todos by the foreign key
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<u64, Error> {
+        let result = t_tags::Entity::delete_many()
+            .filter(t_tags::Column::Id.eq(id))
+            .filter(t_tags::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    pub async fn get_todo_tags(
+        &self,
+        todo_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<t_tags::Model>, Error> {
+        let tags = Self::find_by_todo(todo_id, user_id).all(&self.db).await?;
+        Ok(tags)
+    }
+
+    // Replaces the user's tags on a todo and leaves the tags of other list members alone,
+    // access to the todo and ownership of the tags are checked by the caller
+    pub async fn set_todo_tags(
+        &self,
+        todo_id: Uuid,
+        user_id: Uuid,
+        tag_ids: Vec<Uuid>,
+    ) -> Result<(), Error> {
+        self.db
+            .transaction::<_, (), sea_orm::DbErr>(|txn| {
+                Box::pin(async move {
+                    t_todo_tags::Entity::delete_many()
+                        .filter(t_todo_tags::Column::TodoId.eq(todo_id))
+                        .filter(
+                            t_todo_tags::Column::TagId.in_subquery(
+                                Query::select()
+                                    .column(t_tags::Column::Id)
+                                    .from(t_tags::Entity)
+                                    .and_where(t_tags::Column::UserId.eq(user_id))
+                                    .to_owned(),
+                            ),
+                        )
+                        .exec(txn)
+                        .await?;
+
+                    if tag_ids.is_empty() {
+                        return Ok(());
+                    }
+
+                    t_todo_tags::Entity::insert_many(tag_ids.into_iter().map(|tag_id| {
+                        t_todo_tags::ActiveModel {
+                            todo_id: Set(todo_id),
+                            tag_id: Set(tag_id),
+                        }
+                    }))
+                    .exec_without_returning(txn)
+                    .await?;
+                    Ok(())
+                })
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::fake_database::FakeDatabase;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    #[test]
+    fn test_find_by_todo_joins_through_todo_tags() {
+        let (todo_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let sql = TagRepository::find_by_todo(todo_id, user_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(
+            sql.contains(r#"INNER JOIN "t_todo_tags" ON "t_tags"."id" = "t_todo_tags"."tag_id""#)
+        );
+        assert!(sql.contains(&format!(r#""t_todo_tags"."todo_id" = '{}'"#, todo_id)));
+        assert!(sql.contains(&format!(r#""t_tags"."user_id" = '{}'"#, user_id)));
+    }
+
+    #[tokio::test]
+    async fn test_tag_of_another_user_is_not_found() {
+        let (id, other_user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let database = FakeDatabase::default();
+        let repository = TagRepository::new(database.connect().await);
+
+        assert!(
+            repository
+                .find_by_id(id, other_user_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(repository.delete(id, other_user_id).await.unwrap(), 0);
+
+        let lookup = TagRepository::find_owned(other_user_id)
+            .filter(t_tags::Column::Id.eq(id))
+            .limit(1)
+            .build(DbBackend::Postgres);
+        let statements = database.statements();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], lookup);
+        assert!(
+            statements[1]
+                .to_string()
+                .contains(&format!(r#""t_tags"."user_id" = '{}'"#, other_user_id))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::models::errors::Error;
+use entity::t_todo_items;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Select, TransactionTrait, sea_query::Expr,
+};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+// Number of items of a todo and how many of them are completed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TodoItemCounts {
+    pub total: u64,
+    pub completed: u64,
+}
+
+impl TodoItemCounts {
+    pub fn all_completed(&self) -> bool {
+        self.total > 0 && self.completed == self.total
+    }
+}
+
+// Items have no owner of their own, ownership of the parent todo is checked by the caller
+#[derive(Clone)]
+pub struct TodoItemRepository {
+    pub db: DatabaseConnection,
+}
+
+impl TodoItemRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    // Items created concurrently may share a position, creation order breaks the tie
+    fn find_by_todo(todo_id: Uuid) -> Select<t_todo_items::Entity> {
+        t_todo_items::Entity::find()
+            .filter(t_todo_items::Column::TodoId.eq(todo_id))
+            .order_by_asc(t_todo_items::Column::Position)
+            .order_by_asc(t_todo_items::Column::CreatedAt)
+    }
+
+    fn count_by_todos_query(todo_ids: &[Uuid]) -> Select<t_todo_items::Entity> {
+        t_todo_items::Entity::find()
+            .select_only()
+            .column(t_todo_items::Column::TodoId)
+            .column_as(
+                Expr::col((t_todo_items::Entity, t_todo_items::Column::Id)).count(),
+                "total",
+            )
+            .column_as(
+                Expr::cust(r#"COUNT(*) FILTER (WHERE "t_todo_items"."completed")"#),
+                "completed",
+            )
+            .filter(t_todo_items::Column::TodoId.is_in(todo_ids.iter().copied()))
+            .group_by(t_todo_items::Column::TodoId)
+    }
+
+    pub async fn get_items(&self, todo_id: Uuid) -> Result<Vec<t_todo_items::Model>, Error> {
+        let items = Self::find_by_todo(todo_id).all(&self.db).await?;
+        Ok(items)
+    }
+
+    pub async fn find_by_id(
+        &self,
+        id: Uuid,
+        todo_id: Uuid,
+    ) -> Result<Option<t_todo_items::Model>, Error> {
+        let item = Self::find_by_todo(todo_id)
+            .filter(t_todo_items::Column::Id.eq(id))
+            .one(&self.db)
+            .await?;
+        Ok(item)
+    }
+
+    // New items are appended after the last one
+    pub async fn create(
+        &self,
+        mut item: t_todo_items::ActiveModel,
+        todo_id: Uuid,
+    ) -> Result<t_todo_items::Model, Error> {
+        let last_position: Option<Option<i32>> = t_todo_items::Entity::find()
+            .select_only()
+            .column_as(t_todo_items::Column::Position.max(), "position")
+            .filter(t_todo_items::Column::TodoId.eq(todo_id))
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        item.todo_id = Set(todo_id);
+        item.position = Set(last_position.flatten().map_or(0, |position| position + 1));
+
+        let item = item.insert(&self.db).await?;
+        Ok(item)
+    }
+
+    pub async fn update(
+        &self,
+        item: t_todo_items::ActiveModel,
+    ) -> Result<t_todo_items::Model, Error> {
+        let item = item.update(&self.db).await?;
+        Ok(item)
+    }
+
+    pub async fn delete(&self, id: Uuid, todo_id: Uuid) -> Result<u64, Error> {
+        let result = t_todo_items::Entity::delete_many()
+            .filter(t_todo_items::Column::Id.eq(id))
+            .filter(t_todo_items::Column::TodoId.eq(todo_id))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    // `item_ids` must list every item of the todo exactly once, in their new order
+    pub async fn reorder(
+        &self,
+        todo_id: Uuid,
+        item_ids: Vec<Uuid>,
+    ) -> Result<Vec<t_todo_items::Model>, Error> {
+        let txn = self.db.begin().await?;
+
+        // Locks the items so a concurrent create or delete can't slip between the check and
+        // the updates
+        let current: HashSet<Uuid> = Self::find_by_todo(todo_id)
+            .lock_exclusive()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+
+        let requested: HashSet<Uuid> = item_ids.iter().copied().collect();
+        if requested.len() != item_ids.len() || requested != current {
+            return Err(Error::BadRequest(
+                "itemIds must contain every item of the todo exactly once".to_string(),
+            ));
+        }
+
+        for (position, id) in item_ids.into_iter().enumerate() {
+            t_todo_items::Entity::update_many()
+                .col_expr(t_todo_items::Column::Position, Expr::value(position as i32))
+                .filter(t_todo_items::Column::Id.eq(id))
+                .exec(&txn)
+                .await?;
+        }
+
+        let items = Self::find_by_todo(todo_id).all(&txn).await?;
+        txn.commit().await?;
+        Ok(items)
+    }
+
+    // Todos without items are missing from the map
+    pub async fn count_by_todos(
+        &self,
+        todo_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, TodoItemCounts>, Error> {
+        if todo_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows: Vec<(Uuid, i64, i64)> = Self::count_by_todos_query(todo_ids)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(todo_id, total, completed)| {
+                let counts = TodoItemCounts {
+                    total: total as u64,
+                    completed: completed as u64,
+                };
+                (todo_id, counts)
+            })
+            .collect())
+    }
+
+    pub async fn count_by_todo(&self, todo_id: Uuid) -> Result<TodoItemCounts, Error> {
+        let counts = self.count_by_todos(&[todo_id]).await?;
+        Ok(counts.get(&todo_id).copied().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    #[test]
+    fn test_count_by_todos_groups_completed_items_per_todo() {
+        let todo_id = Uuid::new_v4();
+
+        let sql = TodoItemRepository::count_by_todos_query(&[todo_id])
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#"COUNT("t_todo_items"."id") AS "total""#));
+        assert!(
+            sql.contains(r#"COUNT(*) FILTER (WHERE "t_todo_items"."completed") AS "completed""#)
+        );
+        assert!(sql.contains(&format!(r#""t_todo_items"."todo_id" IN ('{}')"#, todo_id)));
+        assert!(sql.ends_with(r#"GROUP BY "t_todo_items"."todo_id""#));
+    }
+
+    #[test]
+    fn test_all_completed_requires_at_least_one_item() {
+        assert!(!TodoItemCounts::default().all_completed());
+        assert!(
+            !TodoItemCounts {
+                total: 2,
+                completed: 1
+            }
+            .all_completed()
+        );
+        assert!(
+            TodoItemCounts {
+                total: 2,
+                completed: 2
+            }
+            .all_completed()
+        );
+    }
+}
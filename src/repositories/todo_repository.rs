@@ -1,11 +1,49 @@
-use crate::models::errors::Error;
-use entity::t_todos;
+use crate::{
+    models::{
+        db::TodoSearchRow,
+        errors::Error,
+        request::{SortDirection, TagMatch, TodoFilters, TodoSortField},
+    },
+    repositories::list_repository::ListRepository,
+    utils::{cursor::TodoCursor, recurrence::Recurrence},
+    validators::check_schedule,
+};
+use chrono::{DateTime, Utc};
+use entity::{sea_orm_active_enums::ListRole, t_list_members, t_tags, t_todo_tags, t_todos};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, TransactionTrait,
+    ActiveModelTrait,
+    ActiveValue::{self, Set},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DeleteMany, EntityTrait, Order,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, TransactionTrait, UpdateMany,
+    sea_query::{
+        Alias, Expr, Func, NullOrdering, OnConflict, Query, SelectStatement,
+        extension::postgres::PgExpr,
+    },
 };
 use uuid::Uuid;
 
+// Must match the config of the generated "search_vector" column
+const TS_QUERY: &str = "websearch_to_tsquery('english', $1)";
+
+// HTML-escapes a text column, so the only markup in a headline is the <mark> added around matches
+fn escaped_html(column: &str) -> String {
+    format!(
+        r#"replace(replace(replace(replace(replace({}, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')"#,
+        column
+    )
+}
+
+// One write of a batch, already validated and owned by the batch's user
+pub enum TodoWrite {
+    Create(t_todos::ActiveModel),
+    Update {
+        id: Uuid,
+        version: Option<i32>,
+        changes: t_todos::ActiveModel,
+    },
+    Delete(Uuid),
+}
+
 #[derive(Clone)]
 pub struct TodoRepository {
     pub db: DatabaseConnection,
@@ -16,50 +54,1044 @@ impl TodoRepository {
         Self { db }
     }
 
+    // Personal todos of the user, and the todos of the lists where they have at least `role`.
+    // A todo in a list is only reachable through the list, even for the user who created it.
+    fn accessible_by(user_id: Uuid, role: ListRole) -> Condition {
+        Condition::any()
+            .add(
+                Condition::all()
+                    .add(t_todos::Column::ListId.is_null())
+                    .add(t_todos::Column::UserId.eq(user_id)),
+            )
+            .add(
+                t_todos::Column::ListId.in_subquery(ListRepository::member_list_ids(user_id, role)),
+            )
+    }
+
+    // Every todo query starts from the user's access so it can never reach another user's rows.
+    // Soft-deleted todos are only reachable through the trash queries.
+    fn find_accessible(user_id: Uuid) -> Select<t_todos::Entity> {
+        t_todos::Entity::find()
+            .filter(Self::accessible_by(user_id, ListRole::Viewer))
+            .filter(t_todos::Column::DeletedAt.is_null())
+    }
+
+    fn find_accessible_trash(user_id: Uuid) -> Select<t_todos::Entity> {
+        t_todos::Entity::find()
+            .filter(Self::accessible_by(user_id, ListRole::Viewer))
+            .filter(t_todos::Column::DeletedAt.is_not_null())
+            .order_by_desc(t_todos::Column::DeletedAt)
+            .order_by_desc(t_todos::Column::Id)
+    }
+
+    fn find_accessible_by_id(id: Uuid, user_id: Uuid) -> Select<t_todos::Entity> {
+        Self::find_accessible(user_id).filter(t_todos::Column::Id.eq(id))
+    }
+
+    // Case-insensitive substring match, LIKE wildcards in the input are matched literally
+    fn contains_ignore_case(column: t_todos::Column, value: &str) -> Condition {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        Condition::all().add(Expr::col((t_todos::Entity, column)).ilike(format!("%{}%", escaped)))
+    }
+
+    fn find_accessible_matching(user_id: Uuid, filters: &TodoFilters) -> Select<t_todos::Entity> {
+        let query = Self::find_accessible_filtered(user_id, filters);
+        Self::order_by_sort(query, filters)
+    }
+
+    fn find_accessible_filtered(user_id: Uuid, filters: &TodoFilters) -> Select<t_todos::Entity> {
+        let mut query = Self::find_accessible(user_id);
+
+        if let Some(search) = &filters.search {
+            query = query.filter(Self::contains_ignore_case(t_todos::Column::Title, search));
+        }
+
+        if let Some(description) = &filters.description {
+            query = query.filter(Self::contains_ignore_case(
+                t_todos::Column::Description,
+                description,
+            ));
+        }
+
+        if let Some(completed) = filters.completed {
+            query = query.filter(t_todos::Column::Completed.eq(completed));
+        }
+
+        if let Some(created_from) = filters.created_from {
+            query = query.filter(t_todos::Column::CreatedAt.gte(created_from));
+        }
+
+        if let Some(created_to) = filters.created_to {
+            query = query.filter(t_todos::Column::CreatedAt.lte(created_to));
+        }
+
+        if let Some(updated_from) = filters.updated_from {
+            query = query.filter(t_todos::Column::UpdatedAt.gte(updated_from));
+        }
+
+        if let Some(updated_to) = filters.updated_to {
+            query = query.filter(t_todos::Column::UpdatedAt.lte(updated_to));
+        }
+
+        if let Some(priority) = filters.priority {
+            query = query.filter(t_todos::Column::Priority.eq(priority));
+        }
+
+        if let Some(due_from) = filters.due_from {
+            query = query.filter(t_todos::Column::DueAt.gte(due_from));
+        }
+
+        if let Some(due_to) = filters.due_to {
+            query = query.filter(t_todos::Column::DueAt.lte(due_to));
+        }
+
+        if let Some(list_id) = filters.list_id {
+            query = query.filter(t_todos::Column::ListId.eq(list_id));
+        }
+
+        if !filters.tags.is_empty() {
+            query = query
+                .filter(t_todos::Column::Id.in_subquery(Self::tagged_todo_ids(user_id, filters)));
+        }
+
+        query
+    }
+
+    // Ids of the todos tagged with any/all of `filters.tags`, matched by name
+    fn tagged_todo_ids(user_id: Uuid, filters: &TodoFilters) -> SelectStatement {
+        let mut query = Query::select()
+            .column((t_todo_tags::Entity, t_todo_tags::Column::TodoId))
+            .from(t_todo_tags::Entity)
+            .inner_join(
+                t_tags::Entity,
+                Expr::col((t_tags::Entity, t_tags::Column::Id))
+                    .equals((t_todo_tags::Entity, t_todo_tags::Column::TagId)),
+            )
+            .and_where(Expr::col((t_tags::Entity, t_tags::Column::UserId)).eq(user_id))
+            .and_where(
+                Expr::expr(Func::lower(Expr::col((
+                    t_tags::Entity,
+                    t_tags::Column::Name,
+                ))))
+                .is_in(filters.tags.iter().cloned()),
+            )
+            .to_owned();
+
+        if filters.tag_match == TagMatch::All {
+            query
+                .group_by_col((t_todo_tags::Entity, t_todo_tags::Column::TodoId))
+                .and_having(
+                    Expr::expr(Func::count_distinct(Expr::col((
+                        t_tags::Entity,
+                        t_tags::Column::Id,
+                    ))))
+                    .eq(filters.tags.len() as i64),
+                );
+        }
+
+        query
+    }
+
+    fn order_by_sort(
+        query: Select<t_todos::Entity>,
+        filters: &TodoFilters,
+    ) -> Select<t_todos::Entity> {
+        let column = match filters.sort.field {
+            TodoSortField::CreatedAt => t_todos::Column::CreatedAt,
+            TodoSortField::UpdatedAt => t_todos::Column::UpdatedAt,
+            TodoSortField::Title => t_todos::Column::Title,
+            TodoSortField::Completed => t_todos::Column::Completed,
+            TodoSortField::DueAt => t_todos::Column::DueAt,
+            TodoSortField::Priority => t_todos::Column::Priority,
+        };
+
+        let order = match filters.sort.direction {
+            SortDirection::Asc => Order::Asc,
+            SortDirection::Desc => Order::Desc,
+        };
+
+        // Todos without a due date come last in both directions
+        let query = match filters.sort.field {
+            TodoSortField::DueAt => {
+                query.order_by_with_nulls(column, order.clone(), NullOrdering::Last)
+            }
+            _ => query.order_by(column, order.clone()),
+        };
+
+        // id breaks ties so the ordering is stable across pages
+        query.order_by(t_todos::Column::Id, order)
+    }
+
+    // Full-text match ranked by relevance, with highlighted title and description
+    fn search_accessible_matching(
+        user_id: Uuid,
+        text_query: &str,
+        filters: &TodoFilters,
+    ) -> Select<t_todos::Entity> {
+        let query = Self::find_accessible_filtered(user_id, filters)
+            .filter(Expr::cust_with_values(
+                format!(r#""t_todos"."search_vector" @@ {}"#, TS_QUERY),
+                [text_query],
+            ))
+            .column_as(
+                Expr::cust_with_values(
+                    format!(r#"ts_rank("t_todos"."search_vector", {})"#, TS_QUERY),
+                    [text_query],
+                ),
+                "rank",
+            )
+            .column_as(
+                Expr::cust_with_values(
+                    format!(
+                        r#"ts_headline('english', {}, {}, 'StartSel=<mark>, StopSel=</mark>, HighlightAll=true')"#,
+                        escaped_html(r#""t_todos"."title""#),
+                        TS_QUERY
+                    ),
+                    [text_query],
+                ),
+                "title_highlight",
+            )
+            .column_as(
+                Expr::cust_with_values(
+                    format!(
+                        r#"ts_headline('english', {}, {}, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2')"#,
+                        escaped_html(r#""t_todos"."description""#),
+                        TS_QUERY
+                    ),
+                    [text_query],
+                ),
+                "description_highlight",
+            )
+            .order_by(Expr::col(Alias::new("rank")), Order::Desc);
+
+        Self::order_by_sort(query, filters)
+    }
+
+    // Rows strictly after `cursor` in the `created_at DESC, id DESC` ordering
+    fn after_cursor(query: Select<t_todos::Entity>, cursor: TodoCursor) -> Select<t_todos::Entity> {
+        query.filter(
+            Condition::any()
+                .add(t_todos::Column::CreatedAt.lt(cursor.created_at))
+                .add(
+                    Condition::all()
+                        .add(t_todos::Column::CreatedAt.eq(cursor.created_at))
+                        .add(t_todos::Column::Id.lt(cursor.id)),
+                ),
+        )
+    }
+
+    // Moves a todo to the trash (Some) or back out of it (None), viewers of a list can't do either
+    fn set_deleted_at_editable_by_id(
+        id: Uuid,
+        user_id: Uuid,
+        deleted_at: Option<DateTime<Utc>>,
+    ) -> UpdateMany<t_todos::Entity> {
+        let is_in_trash = match deleted_at {
+            Some(_) => t_todos::Column::DeletedAt.is_null(),
+            None => t_todos::Column::DeletedAt.is_not_null(),
+        };
+
+        t_todos::Entity::update_many()
+            .col_expr(t_todos::Column::DeletedAt, Expr::value(deleted_at))
+            .filter(t_todos::Column::Id.eq(id))
+            .filter(Self::accessible_by(user_id, ListRole::Editor))
+            .filter(is_in_trash)
+    }
+
+    // Optimistic locking: only matches while the stored version is still `version`
+    fn update_owned_by_id_if_version(
+        id: Uuid,
+        user_id: Uuid,
+        version: i32,
+        todo: t_todos::ActiveModel,
+    ) -> UpdateMany<t_todos::Entity> {
+        t_todos::Entity::update_many()
+            .set(todo)
+            .col_expr(
+                t_todos::Column::Version,
+                Expr::col(t_todos::Column::Version).add(1),
+            )
+            .filter(t_todos::Column::Id.eq(id))
+            .filter(t_todos::Column::UserId.eq(user_id))
+            .filter(t_todos::Column::DeletedAt.is_null())
+            .filter(t_todos::Column::Version.eq(version))
+    }
+
+    // Checks the resulting schedule and re-arms the reminder when it was moved, so the
+    // reminder job sends it again
+    fn prepare_update(
+        current: &t_todos::Model,
+        mut changes: t_todos::ActiveModel,
+    ) -> Result<UpdateMany<t_todos::Entity>, Error> {
+        let due_at = match &changes.due_at {
+            ActiveValue::Set(due_at) => *due_at,
+            _ => current.due_at,
+        };
+
+        let remind_at = match &changes.remind_at {
+            ActiveValue::Set(remind_at) => *remind_at,
+            _ => current.remind_at,
+        };
+
+        let has_recurrence = match &changes.recurrence {
+            ActiveValue::Set(recurrence) => recurrence.is_some(),
+            _ => current.recurrence.is_some(),
+        };
+
+        check_schedule(due_at, remind_at, has_recurrence)
+            .map_err(|message| Error::BadRequest(message.to_string()))?;
+
+        if remind_at != current.remind_at {
+            changes.reminder_sent_at = Set(None);
+        }
+
+        changes.updated_at = Set(Utc::now());
+
+        Ok(Self::update_owned_by_id_if_version(
+            current.id,
+            current.user_id,
+            current.version,
+            changes,
+        ))
+    }
+
+    // Marks pending reminders as sent in the same statement that selects them, so two
+    // instances running the reminder job never send the same reminder twice
+    fn claim_due_reminders_query(now: DateTime<Utc>) -> UpdateMany<t_todos::Entity> {
+        t_todos::Entity::update_many()
+            .col_expr(t_todos::Column::ReminderSentAt, Expr::value(now))
+            .filter(t_todos::Column::RemindAt.lte(now))
+            .filter(t_todos::Column::ReminderSentAt.is_null())
+            .filter(t_todos::Column::DeletedAt.is_null())
+            .filter(t_todos::Column::Completed.eq(false))
+    }
+
+    // The todo following `todo` in its series, scheduled from its due date with the same
+    // reminder offset. None when the todo doesn't recur or the rule has ended.
+    fn next_occurrence(todo: &t_todos::Model) -> Option<t_todos::ActiveModel> {
+        let due_at = todo.due_at?;
+        let recurrence = match Recurrence::parse(todo.recurrence.as_deref()?) {
+            Ok(recurrence) => recurrence,
+            Err(e) => {
+                log::warn!("todo {} has an invalid recurrence: {}", todo.id, e);
+                return None;
+            }
+        };
+        let series_start_at = todo.series_start_at.unwrap_or(due_at);
+        let next_due_at = recurrence.next_after(due_at, series_start_at)?;
+        let now = Utc::now();
+
+        Some(t_todos::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            title: Set(todo.title.clone()),
+            description: Set(todo.description.clone()),
+            completed: Set(false),
+            created_at: Set(now),
+            updated_at: Set(now),
+            user_id: Set(todo.user_id),
+            due_at: Set(Some(next_due_at)),
+            priority: Set(todo.priority),
+            remind_at: Set(todo
+                .remind_at
+                .map(|remind_at| next_due_at - (due_at - remind_at))),
+            recurrence: Set(todo.recurrence.clone()),
+            series_id: Set(Some(todo.series_id.unwrap_or(todo.id))),
+            series_start_at: Set(Some(series_start_at)),
+            list_id: Set(todo.list_id),
+            ..Default::default()
+        })
+    }
+
+    // Latest occurrence of every active series that is due before `horizon`
+    fn find_series_ending_before(horizon: DateTime<Utc>) -> Select<t_todos::Entity> {
+        t_todos::Entity::find()
+            .filter(t_todos::Column::Recurrence.is_not_null())
+            .filter(t_todos::Column::DeletedAt.is_null())
+            .filter(t_todos::Column::DueAt.lt(horizon))
+            .filter(Expr::cust(
+                r#"NOT EXISTS (SELECT 1 FROM "t_todos" AS "later" WHERE "later"."series_id" = COALESCE("t_todos"."series_id", "t_todos"."id") AND "later"."due_at" > "t_todos"."due_at")"#,
+            ))
+    }
+
+    fn purge_deleted_before_query(cutoff: DateTime<Utc>) -> DeleteMany<t_todos::Entity> {
+        t_todos::Entity::delete_many().filter(t_todos::Column::DeletedAt.lt(cutoff))
+    }
+
+    // Returns one page of todos (page is 0-based) and the total number of matching todos
     pub async fn get_all_todos(
         &self,
+        user_id: Uuid,
         limit: u64,
-        offset: u64,
-        search: Option<String>,
-    ) -> Result<Vec<t_todos::Model>, Error> {
-        let mut query = t_todos::Entity::find();
+        page: u64,
+        filters: &TodoFilters,
+    ) -> Result<(Vec<t_todos::Model>, u64), Error> {
+        let paginator = Self::find_accessible_matching(user_id, filters).paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let todos = paginator.fetch_page(page).await?;
+        Ok((todos, total))
+    }
 
-        if let Some(search) = search {
-            query = query.filter(t_todos::Column::Title.like(format!("%{}%", search)));
+    // Returns one page of full-text search results (page is 0-based) and the total number of matches
+    pub async fn search_todos(
+        &self,
+        user_id: Uuid,
+        limit: u64,
+        page: u64,
+        text_query: &str,
+        filters: &TodoFilters,
+    ) -> Result<(Vec<TodoSearchRow>, u64), Error> {
+        let paginator = Self::search_accessible_matching(user_id, text_query, filters)
+            .into_model::<TodoSearchRow>()
+            .paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let rows = paginator.fetch_page(page).await?;
+        Ok((rows, total))
+    }
+
+    // Keyset pagination, returns up to `limit` todos after `cursor` and whether more remain
+    pub async fn get_todos_after_cursor(
+        &self,
+        user_id: Uuid,
+        limit: u64,
+        cursor: Option<TodoCursor>,
+        filters: &TodoFilters,
+    ) -> Result<(Vec<t_todos::Model>, bool), Error> {
+        let mut query = Self::find_accessible_matching(user_id, filters);
+
+        if let Some(cursor) = cursor {
+            query = Self::after_cursor(query, cursor);
         }
 
-        let todos = query
-            .order_by_desc(t_todos::Column::CreatedAt)
-            .paginate(&self.db, limit)
-            .fetch_page(offset)
+        // Fetch one extra row to know if there is a next page
+        let mut todos = query.limit(limit + 1).all(&self.db).await?;
+        let has_next = todos.len() as u64 > limit;
+        todos.truncate(limit as usize);
+
+        Ok((todos, has_next))
+    }
+
+    pub async fn find_by_id(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<t_todos::Model>, Error> {
+        let todo = Self::find_accessible_by_id(id, user_id)
+            .one(&self.db)
             .await?;
-        Ok(todos)
+        Ok(todo)
     }
 
-    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<t_todos::Model>, Error> {
-        let todo = t_todos::Entity::find_by_id(id).one(&self.db).await?;
+    // Everyone who can see the todo: the members of its list, or its owner for a personal todo
+    pub async fn find_audience(&self, todo: &t_todos::Model) -> Result<Vec<Uuid>, Error> {
+        let Some(list_id) = todo.list_id else {
+            return Ok(vec![todo.user_id]);
+        };
+
+        let user_ids = t_list_members::Entity::find()
+            .select_only()
+            .column(t_list_members::Column::UserId)
+            .filter(t_list_members::Column::ListId.eq(list_id))
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+        Ok(user_ids)
+    }
+
+    // Like find_by_id, but viewers of the todo's list are refused with Forbidden
+    pub async fn find_editable_by_id(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<t_todos::Model, Error> {
+        Self::find_editable(&self.db, id, user_id).await
+    }
+
+    pub async fn check_list_access(&self, list_id: Uuid, user_id: Uuid) -> Result<(), Error> {
+        Self::check_can_add_to_list(&self.db, list_id, user_id).await
+    }
+
+    async fn find_editable<C: ConnectionTrait>(
+        conn: &C,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<t_todos::Model, Error> {
+        let todo = Self::find_accessible_by_id(id, user_id)
+            .one(conn)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", id)))?;
+
+        if let Some(list_id) = todo.list_id {
+            Self::check_can_add_to_list(conn, list_id, user_id).await?;
+        }
+
         Ok(todo)
     }
 
+    // Editors and owners of a list can add, change and delete its todos
+    async fn check_can_add_to_list<C: ConnectionTrait>(
+        conn: &C,
+        list_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), Error> {
+        match ListRepository::find_role(conn, list_id, user_id).await? {
+            Some(role) if role >= ListRole::Editor => Ok(()),
+            Some(_) => Err(Error::ForbiddenWithMessage(
+                "Editor access to the list is required".to_string(),
+            )),
+            None => Err(Error::NotFound(format!(
+                "List with id {} not found",
+                list_id
+            ))),
+        }
+    }
+
     pub async fn create(&self, todo: t_todos::ActiveModel) -> Result<t_todos::Model, Error> {
         let todo = todo.insert(&self.db).await?;
         Ok(todo)
     }
 
-    pub async fn update(&self, todo: t_todos::ActiveModel) -> Result<t_todos::Model, Error> {
-        let todo = todo.update(&self.db).await?;
-        Ok(todo)
+    // Returns None if the todo is gone or was updated by someone else since `current` was read
+    pub async fn update(
+        &self,
+        current: &t_todos::Model,
+        changes: t_todos::ActiveModel,
+    ) -> Result<Option<t_todos::Model>, Error> {
+        let todos = Self::prepare_update(current, changes)?
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(todos.into_iter().next())
+    }
+
+    // Runs the writes in a single transaction and returns one result per executed write.
+    // Atomic batches stop and roll back at the first failure, otherwise every write gets its
+    // own savepoint so a failed write is discarded without affecting the others.
+    // Successful writes yield the todo before (None for creates) and after the write.
+    pub async fn apply_batch(
+        &self,
+        user_id: Uuid,
+        writes: Vec<TodoWrite>,
+        atomic: bool,
+    ) -> Result<Vec<Result<(Option<t_todos::Model>, t_todos::Model), Error>>, Error> {
+        let txn = self.db.begin().await?;
+        let mut results = Vec::with_capacity(writes.len());
+
+        for write in writes {
+            let result = if atomic {
+                Self::apply_write(&txn, user_id, write).await
+            } else {
+                let savepoint = txn.begin().await?;
+                let result = Self::apply_write(&savepoint, user_id, write).await;
+
+                match result {
+                    Ok(_) => savepoint.commit().await?,
+                    Err(_) => savepoint.rollback().await?,
+                }
+                result
+            };
+
+            let failed = result.is_err();
+            results.push(result);
+
+            if atomic && failed {
+                txn.rollback().await?;
+                return Ok(results);
+            }
+        }
+
+        txn.commit().await?;
+        Ok(results)
+    }
+
+    async fn apply_write<C: ConnectionTrait>(
+        conn: &C,
+        user_id: Uuid,
+        write: TodoWrite,
+    ) -> Result<(Option<t_todos::Model>, t_todos::Model), Error> {
+        match write {
+            TodoWrite::Create(mut todo) => {
+                if let ActiveValue::Set(Some(list_id)) = todo.list_id {
+                    Self::check_can_add_to_list(conn, list_id, user_id).await?;
+                }
+
+                todo.user_id = Set(user_id);
+                Ok((None, todo.insert(conn).await?))
+            }
+            TodoWrite::Update {
+                id,
+                version,
+                changes,
+            } => {
+                let todo = Self::find_editable(conn, id, user_id).await?;
+
+                let modified =
+                    || Error::PreconditionFailed(format!("Todo with id {} has been modified", id));
+
+                if version.is_some_and(|version| version != todo.version) {
+                    return Err(modified());
+                }
+
+                let updated = Self::prepare_update(&todo, changes)?
+                    .exec_with_returning(conn)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(modified)?;
+                Ok((Some(todo), updated))
+            }
+            TodoWrite::Delete(id) => {
+                let todo = Self::find_editable(conn, id, user_id).await?;
+
+                let deleted = Self::set_deleted_at_editable_by_id(id, user_id, Some(Utc::now()))
+                    .exec_with_returning(conn)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", id)))?;
+                Ok((Some(todo), deleted))
+            }
+        }
+    }
+
+    // Returns the deleted todo, None if it doesn't exist or belongs to someone else
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<Option<t_todos::Model>, Error> {
+        let todos = Self::set_deleted_at_editable_by_id(id, user_id, Some(Utc::now()))
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(todos.into_iter().next())
+    }
+
+    // Returns the restored todo, None if it isn't in the caller's trash
+    pub async fn restore(&self, id: Uuid, user_id: Uuid) -> Result<Option<t_todos::Model>, Error> {
+        let todos = Self::set_deleted_at_editable_by_id(id, user_id, None)
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(todos.into_iter().next())
+    }
+
+    // Returns one page of trashed todos (page is 0-based) and the total in the trash
+    pub async fn get_trash(
+        &self,
+        user_id: Uuid,
+        limit: u64,
+        page: u64,
+    ) -> Result<(Vec<t_todos::Model>, u64), Error> {
+        let paginator = Self::find_accessible_trash(user_id).paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let todos = paginator.fetch_page(page).await?;
+        Ok((todos, total))
     }
 
-    pub async fn delete(&self, id: Uuid) -> Result<(), Error> {
-        self.db
-            .transaction::<_, (), sea_orm::DbErr>(|txn| {
-                Box::pin(async move {
-                    t_todos::Entity::delete_by_id(id).exec(txn).await?;
-                    Ok(())
-                })
-            })
+    // Returns None if the series has ended or the occurrence already exists
+    pub async fn create_next_occurrence(
+        &self,
+        todo: &t_todos::Model,
+    ) -> Result<Option<t_todos::Model>, Error> {
+        let Some(next) = Self::next_occurrence(todo) else {
+            return Ok(None);
+        };
+
+        let todos = t_todos::Entity::insert(next)
+            .on_conflict(
+                OnConflict::columns([t_todos::Column::SeriesId, t_todos::Column::DueAt])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_with_returning_many(&self.db)
+            .await?;
+        Ok(todos.into_iter().next())
+    }
+
+    // Creates the next occurrence of every series whose latest occurrence is due before
+    // `horizon`, until each series reaches it. Returns the number of created todos.
+    pub async fn materialize_occurrences(&self, horizon: DateTime<Utc>) -> Result<u64, Error> {
+        // Bounds the work of one run for rules with a very short period
+        const MAX_ROUNDS: usize = 100;
+        let mut created = 0;
+
+        for _ in 0..MAX_ROUNDS {
+            let todos = Self::find_series_ending_before(horizon)
+                .all(&self.db)
+                .await?;
+
+            let mut created_in_round = 0;
+            for todo in &todos {
+                if self.create_next_occurrence(todo).await?.is_some() {
+                    created_in_round += 1;
+                }
+            }
+
+            // Only ended series are left
+            if created_in_round == 0 {
+                break;
+            }
+            created += created_in_round;
+        }
+
+        Ok(created)
+    }
+
+    pub async fn claim_due_reminders(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<t_todos::Model>, Error> {
+        let todos = Self::claim_due_reminders_query(now)
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(todos)
+    }
+
+    // Gives a claimed reminder back to the job, e.g. when its notification couldn't be queued
+    pub async fn release_reminder(&self, id: Uuid) -> Result<(), Error> {
+        t_todos::Entity::update_many()
+            .col_expr(
+                t_todos::Column::ReminderSentAt,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .filter(t_todos::Column::Id.eq(id))
+            .exec(&self.db)
             .await?;
         Ok(())
     }
+
+    // Permanently removes todos that have been in the trash since before `cutoff`
+    pub async fn purge_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<u64, Error> {
+        let result = Self::purge_deleted_before_query(cutoff)
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::request::TodoSort, repositories::fake_database::FakeDatabase};
+    use entity::sea_orm_active_enums::TodoPriority;
+    use sea_orm::{DbBackend, QueryTrait};
+
+    fn todo(user_id: Uuid, list_id: Option<Uuid>) -> t_todos::Model {
+        let now = Utc::now();
+        t_todos::Model {
+            id: Uuid::new_v4(),
+            title: "Buy milk".to_string(),
+            description: String::new(),
+            completed: false,
+            created_at: now,
+            updated_at: now,
+            user_id,
+            deleted_at: None,
+            version: 1,
+            due_at: None,
+            priority: TodoPriority::Medium,
+            remind_at: None,
+            reminder_sent_at: None,
+            recurrence: None,
+            series_id: None,
+            series_start_at: None,
+            list_id,
+        }
+    }
+
+    #[test]
+    fn test_find_accessible_by_id_filters_on_owner() {
+        let (id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let sql = TodoRepository::find_accessible_by_id(id, user_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(&format!(r#""t_todos"."user_id" = '{}'"#, user_id)));
+        assert!(sql.contains(&format!(r#""t_todos"."id" = '{}'"#, id)));
+    }
+
+    #[test]
+    fn test_find_accessible_includes_shared_lists() {
+        let user_id = Uuid::new_v4();
+
+        let sql = TodoRepository::find_accessible(user_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Personal todos only match outside of lists, list todos need a membership
+        assert!(sql.contains(&format!(
+            r#"("t_todos"."list_id" IS NULL AND "t_todos"."user_id" = '{}')"#,
+            user_id
+        )));
+        assert!(sql.contains(&format!(
+            r#""t_todos"."list_id" IN (SELECT "t_list_members"."list_id" FROM "t_list_members" WHERE "t_list_members"."user_id" = '{}' AND "t_list_members"."role" >= 1)"#,
+            user_id
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_todo_of_another_user_is_not_found() {
+        let (id, other_user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let database = FakeDatabase::default();
+        let repository = TodoRepository::new(database.connect().await);
+
+        let result = repository.find_editable_by_id(id, other_user_id).await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+        assert!(
+            repository
+                .find_by_id(id, other_user_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            repository
+                .delete(id, other_user_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Every lookup was scoped to the caller
+        let lookup = TodoRepository::find_accessible_by_id(id, other_user_id)
+            .limit(1)
+            .build(DbBackend::Postgres);
+        let statements = database.statements();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0], lookup);
+        assert_eq!(statements[1], lookup);
+    }
+
+    #[tokio::test]
+    async fn test_list_todo_needs_an_editor_to_change() {
+        let (user_id, list_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let todo = todo(Uuid::new_v4(), Some(list_id));
+        let member = |role| t_list_members::Model {
+            list_id,
+            user_id,
+            role,
+            created_at: Utc::now(),
+        };
+        let database = FakeDatabase::default();
+        let repository = TodoRepository::new(database.connect().await);
+
+        database
+            .push_rows(vec![todo.clone()])
+            .push_rows(vec![member(ListRole::Viewer)]);
+        let viewer = repository.find_editable_by_id(todo.id, user_id).await;
+        assert!(matches!(viewer, Err(Error::ForbiddenWithMessage(_))));
+
+        database
+            .push_rows(vec![todo.clone()])
+            .push_rows(vec![member(ListRole::Editor)]);
+        let editor = repository.find_editable_by_id(todo.id, user_id).await;
+        assert_eq!(editor.unwrap(), todo);
+
+        // Left the list since reading the todo
+        database.push_rows(vec![todo.clone()]);
+        let former_member = repository.find_editable_by_id(todo.id, user_id).await;
+        assert!(matches!(former_member, Err(Error::NotFound(_))));
+    }
+
+    #[test]
+    fn test_soft_delete_filters_on_owner() {
+        let (id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let sql = TodoRepository::set_deleted_at_editable_by_id(id, user_id, Some(Utc::now()))
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.starts_with(r#"UPDATE "t_todos" SET "deleted_at" = "#));
+        assert!(sql.contains(&format!(r#""t_todos"."user_id" = '{}'"#, user_id)));
+        assert!(sql.contains(&format!(r#""t_todos"."id" = '{}'"#, id)));
+        assert!(sql.contains(r#""t_list_members"."role" >= 2"#));
+        assert!(sql.ends_with(r#""t_todos"."deleted_at" IS NULL"#));
+    }
+
+    #[test]
+    fn test_update_is_conditional_on_version() {
+        let (id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let todo = t_todos::ActiveModel {
+            title: Set("Buy milk".to_string()),
+            ..Default::default()
+        };
+
+        let sql = TodoRepository::update_owned_by_id_if_version(id, user_id, 3, todo)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.starts_with(
+            r#"UPDATE "t_todos" SET "title" = 'Buy milk', "version" = "version" + 1 WHERE"#
+        ));
+        assert!(sql.contains(&format!(r#""t_todos"."user_id" = '{}'"#, user_id)));
+        assert!(sql.ends_with(r#""t_todos"."version" = 3"#));
+    }
+
+    #[test]
+    fn test_find_accessible_excludes_trash() {
+        let sql = TodoRepository::find_accessible(Uuid::new_v4())
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#""t_todos"."deleted_at" IS NULL"#));
+    }
+
+    #[test]
+    fn test_after_cursor_uses_keyset_condition() {
+        let cursor = TodoCursor::new(Utc::now(), Uuid::new_v4());
+
+        let sql = TodoRepository::after_cursor(
+            TodoRepository::find_accessible_matching(Uuid::new_v4(), &TodoFilters::default()),
+            cursor,
+        )
+        .build(DbBackend::Postgres)
+        .to_string();
+
+        assert!(sql.contains(r#""t_todos"."created_at" < "#));
+        assert!(sql.contains(&format!(r#""t_todos"."id" < '{}'"#, cursor.id)));
+        assert!(sql.ends_with(r#"ORDER BY "t_todos"."created_at" DESC, "t_todos"."id" DESC"#));
+    }
+
+    #[test]
+    fn test_find_accessible_matching_translates_filters() {
+        let filters = TodoFilters {
+            search: Some("50%_off".to_string()),
+            completed: Some(true),
+            sort: TodoSort::parse("title:asc").unwrap(),
+            ..Default::default()
+        };
+
+        let sql = TodoRepository::find_accessible_matching(Uuid::new_v4(), &filters)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#""t_todos"."title" ILIKE E'%50\\%\\_off%'"#));
+        assert!(sql.contains(r#""t_todos"."completed" = TRUE"#));
+        assert!(sql.ends_with(r#"ORDER BY "t_todos"."title" ASC, "t_todos"."id" ASC"#));
+    }
+
+    #[test]
+    fn test_find_accessible_matching_sorts_by_due_date_nulls_last() {
+        let filters = TodoFilters {
+            priority: Some(TodoPriority::High),
+            sort: TodoSort::parse("dueAt:asc").unwrap(),
+            ..Default::default()
+        };
+
+        let sql = TodoRepository::find_accessible_matching(Uuid::new_v4(), &filters)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#""t_todos"."priority" = 3"#));
+        assert!(sql.ends_with(r#"ORDER BY "t_todos"."due_at" ASC NULLS LAST, "t_todos"."id" ASC"#));
+    }
+
+    #[test]
+    fn test_tag_filter_matches_any_or_all_tags() {
+        let user_id = Uuid::new_v4();
+        let mut filters = TodoFilters {
+            tags: vec!["urgent".to_string(), "work".to_string()],
+            ..Default::default()
+        };
+
+        let sql = TodoRepository::find_accessible_matching(user_id, &filters)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(
+            sql.contains(r#""t_todos"."id" IN (SELECT "t_todo_tags"."todo_id" FROM "t_todo_tags""#)
+        );
+        assert!(sql.contains(&format!(r#""t_tags"."user_id" = '{}'"#, user_id)));
+        assert!(sql.contains(r#"LOWER("t_tags"."name") IN ('urgent', 'work')"#));
+        assert!(!sql.contains("HAVING"));
+
+        filters.tag_match = TagMatch::All;
+        let sql = TodoRepository::find_accessible_matching(user_id, &filters)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#"HAVING COUNT(DISTINCT "t_tags"."id") = 2"#));
+    }
+
+    #[test]
+    fn test_claim_due_reminders_skips_sent_and_finished_todos() {
+        let sql = TodoRepository::claim_due_reminders_query(Utc::now())
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.starts_with(r#"UPDATE "t_todos" SET "reminder_sent_at" = "#));
+        assert!(sql.contains(r#""t_todos"."reminder_sent_at" IS NULL"#));
+        assert!(sql.contains(r#""t_todos"."deleted_at" IS NULL"#));
+        assert!(sql.contains(r#""t_todos"."completed" = FALSE"#));
+    }
+
+    #[test]
+    fn test_next_occurrence_continues_the_series() {
+        let due_at = Utc::now();
+        let todo = t_todos::Model {
+            id: Uuid::new_v4(),
+            title: "Take out the trash".to_string(),
+            description: "Every week".to_string(),
+            completed: true,
+            created_at: due_at,
+            updated_at: due_at,
+            user_id: Uuid::new_v4(),
+            deleted_at: None,
+            version: 3,
+            due_at: Some(due_at),
+            priority: TodoPriority::High,
+            remind_at: Some(due_at - chrono::Duration::hours(1)),
+            reminder_sent_at: Some(due_at),
+            recurrence: Some("RRULE:FREQ=WEEKLY".to_string()),
+            series_id: None,
+            series_start_at: None,
+            list_id: Some(Uuid::new_v4()),
+        };
+
+        let next = TodoRepository::next_occurrence(&todo).unwrap();
+        let next_due_at = due_at + chrono::Duration::weeks(1);
+
+        assert_eq!(next.series_id, Set(Some(todo.id)));
+        assert_eq!(next.list_id, Set(todo.list_id));
+        assert_eq!(next.completed, Set(false));
+        assert_eq!(next.due_at, Set(Some(next_due_at)));
+        assert_eq!(
+            next.remind_at,
+            Set(Some(next_due_at - chrono::Duration::hours(1)))
+        );
+
+        let ended = t_todos::Model {
+            recurrence: Some("RRULE:FREQ=WEEKLY;UNTIL=20000101T000000Z".to_string()),
+            ..todo
+        };
+        assert!(TodoRepository::next_occurrence(&ended).is_none());
+    }
+
+    #[test]
+    fn test_todo_sort_rejects_unknown_columns() {
+        assert!(TodoSort::parse("password").is_none());
+        assert!(TodoSort::parse("title:sideways").is_none());
+        assert_eq!(TodoSort::parse("createdAt"), Some(TodoSort::default()));
+    }
+
+    #[test]
+    fn test_search_accessible_matching_ranks_full_text_matches() {
+        let sql = TodoRepository::search_accessible_matching(
+            Uuid::new_v4(),
+            "olive oil",
+            &TodoFilters::default(),
+        )
+        .build(DbBackend::Postgres)
+        .to_string();
+
+        assert!(sql.contains(
+            r#""t_todos"."search_vector" @@ websearch_to_tsquery('english', 'olive oil')"#
+        ));
+        assert!(sql.contains(r#"AS "title_highlight""#));
+        assert!(sql.contains(
+            r#"ts_headline('english', replace(replace(replace(replace(replace("t_todos"."title", '&', '&amp;'), '<', '&lt;')"#
+        ));
+        assert!(sql.contains(r#"ORDER BY "rank" DESC, "t_todos"."created_at" DESC"#));
+    }
 }
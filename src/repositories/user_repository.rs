@@ -1,8 +1,18 @@
 use crate::models::errors::Error;
-use entity::t_users;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use entity::{t_permissions, t_role_permissions, t_roles, t_user_roles, t_users};
+use sea_orm::{
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+    sea_query::{Expr, Func, OnConflict, Query},
+};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+// Role every account gets at sign-up
+pub const DEFAULT_ROLE: &str = "user";
+
 #[derive(Clone)]
 pub struct UserRepository {
     pub db: DatabaseConnection,
@@ -21,16 +31,163 @@ impl UserRepository {
         Ok(user)
     }
 
+    // Case-insensitive, emails are stored as they were typed at sign-up.
+    // Served by the unique index on LOWER("email").
     pub async fn get_user_by_email(&self, email: &str) -> Result<Option<t_users::Model>, Error> {
         let user = t_users::Entity::find()
-            .filter(t_users::Column::Email.eq(email))
+            .filter(
+                Expr::expr(Func::lower(Expr::col(t_users::Column::Email)))
+                    .eq(email.trim().to_lowercase()),
+            )
             .one(&self.db)
             .await?;
         Ok(user)
     }
 
+    // Returns one page of users (page is 0-based) and the total number of users
+    pub async fn get_all_users(
+        &self,
+        limit: u64,
+        page: u64,
+    ) -> Result<(Vec<t_users::Model>, u64), Error> {
+        let paginator = t_users::Entity::find()
+            .order_by_desc(t_users::Column::CreatedAt)
+            .paginate(&self.db, limit);
+
+        let total = paginator.num_items().await?;
+        let users = paginator.fetch_page(page).await?;
+        Ok((users, total))
+    }
+
+    pub async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<String>, Error> {
+        let roles = t_roles::Entity::find()
+            .inner_join(t_user_roles::Entity)
+            .filter(t_user_roles::Column::UserId.eq(user_id))
+            .order_by_asc(t_roles::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(roles.into_iter().map(|role| role.name).collect())
+    }
+
+    // Permissions granted by any of the user's roles
+    pub async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<String>, Error> {
+        let permissions = t_permissions::Entity::find()
+            .filter(
+                t_permissions::Column::Id.in_subquery(
+                    Query::select()
+                        .column(t_role_permissions::Column::PermissionId)
+                        .from(t_role_permissions::Entity)
+                        .inner_join(
+                            t_user_roles::Entity,
+                            Expr::col((t_user_roles::Entity, t_user_roles::Column::RoleId)).equals(
+                                (
+                                    t_role_permissions::Entity,
+                                    t_role_permissions::Column::RoleId,
+                                ),
+                            ),
+                        )
+                        .and_where(t_user_roles::Column::UserId.eq(user_id))
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(t_permissions::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(permissions
+            .into_iter()
+            .map(|permission| permission.name)
+            .collect())
+    }
+
+    pub async fn get_roles(
+        &self,
+    ) -> Result<Vec<(t_roles::Model, Vec<t_permissions::Model>)>, Error> {
+        let roles = t_roles::Entity::find()
+            .find_with_related(t_permissions::Entity)
+            .order_by_asc(t_roles::Column::Name)
+            .order_by_asc(t_permissions::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(roles)
+    }
+
+    pub async fn find_role_by_name(&self, name: &str) -> Result<Option<t_roles::Model>, Error> {
+        let role = t_roles::Entity::find()
+            .filter(t_roles::Column::Name.eq(name))
+            .one(&self.db)
+            .await?;
+        Ok(role)
+    }
+
+    // Returns false if the user already had the role
+    pub async fn add_user_role(&self, user_id: Uuid, role_id: i32) -> Result<bool, Error> {
+        let inserted = t_user_roles::Entity::insert(t_user_roles::ActiveModel {
+            user_id: Set(user_id),
+            role_id: Set(role_id),
+        })
+        .on_conflict(
+            OnConflict::columns([t_user_roles::Column::UserId, t_user_roles::Column::RoleId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&self.db)
+        .await?;
+        Ok(inserted > 0)
+    }
+
+    // Returns false if the user didn't have the role
+    pub async fn remove_user_role(&self, user_id: Uuid, role_id: i32) -> Result<bool, Error> {
+        let result = t_user_roles::Entity::delete_by_id((user_id, role_id))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn get_roles_by_user_ids(
+        &self,
+        user_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, Vec<String>>, Error> {
+        let user_roles = t_user_roles::Entity::find()
+            .find_also_related(t_roles::Entity)
+            .filter(t_user_roles::Column::UserId.is_in(user_ids))
+            .order_by_asc(t_roles::Column::Name)
+            .all(&self.db)
+            .await?;
+
+        let mut roles_by_user: HashMap<Uuid, Vec<String>> = HashMap::new();
+        for (user_role, role) in user_roles {
+            if let Some(role) = role {
+                roles_by_user
+                    .entry(user_role.user_id)
+                    .or_default()
+                    .push(role.name);
+            }
+        }
+        Ok(roles_by_user)
+    }
+
+    // New accounts get DEFAULT_ROLE in the same transaction
     pub async fn create_user(&self, user: t_users::ActiveModel) -> Result<t_users::Model, Error> {
-        let user = user.insert(&self.db).await?;
+        let txn = self.db.begin().await?;
+        let user = user.insert(&txn).await?;
+
+        let add_default_role = Query::insert()
+            .into_table(t_user_roles::Entity)
+            .columns([t_user_roles::Column::UserId, t_user_roles::Column::RoleId])
+            .select_from(
+                Query::select()
+                    .expr(Expr::val(user.id))
+                    .column(t_roles::Column::Id)
+                    .from(t_roles::Entity)
+                    .and_where(t_roles::Column::Name.eq(DEFAULT_ROLE))
+                    .to_owned(),
+            )
+            .map_err(|e| Error::InternalServerError(e.to_string()))?
+            .to_owned();
+        txn.execute(txn.get_database_backend().build(&add_default_role))
+            .await?;
+
+        txn.commit().await?;
         Ok(user)
     }
 
@@ -0,0 +1,230 @@
+use crate::{
+    config,
+    models::{db::User, errors::Error, request::GetAuditLogRequest, response::GetAuditLogResponse},
+    repositories::audit_log_repository::AuditLogRepository,
+};
+use chrono::Utc;
+use entity::{
+    sea_orm_active_enums::{AuditAction, AuditEntityType},
+    t_audit_log, t_todos,
+};
+use sea_orm::ActiveValue::Set;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+// Changed on every write, so it would show up in every diff
+const IGNORED_FIELDS: [&str; 1] = ["updatedAt"];
+
+#[derive(Clone)]
+pub struct AuditService {
+    pub audit_log_repository: AuditLogRepository,
+}
+
+impl AuditService {
+    pub fn new(audit_log_repository: AuditLogRepository) -> Self {
+        Self {
+            audit_log_repository,
+        }
+    }
+
+    // `{ field: { before, after } }` for every field that differs, None when nothing changed.
+    // A missing side (create, delete) counts as null for all of its fields.
+    pub fn diff<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Option<Value> {
+        let to_map = |value: Option<&T>| match value.map(serde_json::to_value) {
+            Some(Ok(Value::Object(map))) => map,
+            _ => Map::new(),
+        };
+        let (before, after) = (to_map(before), to_map(after));
+
+        let mut changes = Map::new();
+        for key in before.keys().chain(after.keys()) {
+            if IGNORED_FIELDS.contains(&key.as_str()) || changes.contains_key(key) {
+                continue;
+            }
+
+            let (old, new) = (before.get(key), after.get(key));
+            if old != new {
+                changes.insert(
+                    key.clone(),
+                    json!({ "before": old.unwrap_or(&Value::Null), "after": new.unwrap_or(&Value::Null) }),
+                );
+            }
+        }
+
+        (!changes.is_empty()).then_some(Value::Object(changes))
+    }
+
+    // Auditing never fails the audited request, a lost entry is logged instead
+    pub async fn record(
+        &self,
+        actor_id: Uuid,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        action: AuditAction,
+        changes: Option<Value>,
+        ip: &str,
+    ) {
+        let entry = t_audit_log::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            actor_id: Set(Some(actor_id)),
+            entity_type: Set(entity_type),
+            entity_id: Set(entity_id),
+            action: Set(action),
+            changes: Set(changes),
+            ip: Set(Some(ip.to_string())),
+            created_at: Set(Utc::now()),
+        };
+
+        if let Err(e) = self.audit_log_repository.create(entry).await {
+            log::error!(
+                "failed to record {:?} of {:?} {}: {:?}",
+                action,
+                entity_type,
+                entity_id,
+                e
+            );
+        }
+    }
+
+    pub async fn record_todo(
+        &self,
+        actor_id: Uuid,
+        action: AuditAction,
+        before: Option<&t_todos::Model>,
+        after: Option<&t_todos::Model>,
+        ip: &str,
+    ) {
+        let Some(todo_id) = after.or(before).map(|todo| todo.id) else {
+            return;
+        };
+
+        let changes = Self::diff(before, after);
+        self.record(
+            actor_id,
+            AuditEntityType::Todo,
+            todo_id,
+            action,
+            changes,
+            ip,
+        )
+        .await;
+    }
+
+    // The actor is the user themself or an admin acting on the account.
+    // The password hash isn't part of `User`, a change of it is recorded without values.
+    pub async fn record_user(
+        &self,
+        actor_id: Uuid,
+        action: AuditAction,
+        before: Option<&User>,
+        after: Option<&User>,
+        password_changed: bool,
+        ip: &str,
+    ) {
+        let Some(user_id) = after.or(before).map(|user| user.id) else {
+            return;
+        };
+
+        let mut changes = Self::diff(before, after);
+        if password_changed && let Value::Object(map) = changes.get_or_insert_with(|| json!({})) {
+            map.insert(
+                "password".to_string(),
+                json!({ "before": "[redacted]", "after": "[redacted]" }),
+            );
+        }
+
+        self.record(
+            actor_id,
+            AuditEntityType::User,
+            user_id,
+            action,
+            changes,
+            ip,
+        )
+        .await;
+    }
+
+    pub async fn get_entity_history(
+        &self,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        params: GetAuditLogRequest,
+    ) -> Result<GetAuditLogResponse, Error> {
+        let page_size = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
+        let page = params.page.unwrap_or(*config::DEFAULT_PAGE);
+
+        let (entries, total) = self
+            .audit_log_repository
+            .get_entity_history(entity_type, entity_id, page_size, page.saturating_sub(1))
+            .await?;
+
+        Ok(GetAuditLogResponse::new(page, page_size, total, entries))
+    }
+
+    pub async fn get_actor_activity(
+        &self,
+        actor_id: Uuid,
+        params: GetAuditLogRequest,
+    ) -> Result<GetAuditLogResponse, Error> {
+        let page_size = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
+        let page = params.page.unwrap_or(*config::DEFAULT_PAGE);
+
+        let (entries, total) = self
+            .audit_log_repository
+            .get_actor_activity(actor_id, page_size, page.saturating_sub(1))
+            .await?;
+
+        Ok(GetAuditLogResponse::new(page, page_size, total, entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Todo {
+        title: String,
+        completed: bool,
+        #[serde(rename = "updatedAt")]
+        updated_at: i64,
+    }
+
+    #[test]
+    fn test_diff_only_keeps_changed_fields() {
+        let before = Todo {
+            title: "Buy milk".to_string(),
+            completed: false,
+            updated_at: 1,
+        };
+        let after = Todo {
+            title: "Buy milk".to_string(),
+            completed: true,
+            updated_at: 2,
+        };
+
+        assert_eq!(
+            AuditService::diff(Some(&before), Some(&after)),
+            Some(json!({ "completed": { "before": false, "after": true } }))
+        );
+        assert_eq!(AuditService::diff(Some(&after), Some(&after)), None);
+    }
+
+    #[test]
+    fn test_diff_of_a_creation_has_no_before() {
+        let todo = Todo {
+            title: "Buy milk".to_string(),
+            completed: false,
+            updated_at: 1,
+        };
+
+        assert_eq!(
+            AuditService::diff(None, Some(&todo)),
+            Some(json!({
+                "title": { "before": null, "after": "Buy milk" },
+                "completed": { "before": null, "after": false },
+            }))
+        );
+    }
+}
@@ -1,62 +1,155 @@
 use crate::{
     config,
+    daos::redis_dao::{RedisDao, Value},
     models::{
         db::User,
         errors::Error,
-        request::{SignInRequest, SignUpRequest, UpdateUserRequest},
+        request::{
+            ForgotPasswordRequest, GetAllUsersRequest, GetAuditLogRequest, PatchUserRequest,
+            ResendVerificationRequest, ResetPasswordRequest, SignInRequest, SignUpRequest,
+            UpdateUserRequest, VerifyEmailRequest,
+        },
         response::{
-            MeResponse, RefreshTokenResponse, SignInResponse, SignUpResponse, UpdateUserResponse,
+            AdminUser, CommonResponse, GetAllUsersResponse, GetAuditLogResponse, GetRolesResponse,
+            MeResponse, RefreshTokenResponse, RoleResponse, SignInResponse, SignUpResponse,
+            UpdateUserResponse,
         },
     },
     repositories::{
         refresh_token_repository::RefreshTokenRepository, user_repository::UserRepository,
     },
+    services::{
+        audit_service::AuditService,
+        mail_service::MailService,
+        notification_service::{Notification, NotificationService},
+    },
     utils::{
-        hash::{hash_password, verify_password},
-        jwt::JwtClaims,
+        hash::{hash_password, hash_token, verify_password},
+        jwt::{ActionTokenClaims, JwtClaims, TokenPurpose, verify_action_token},
     },
 };
 use chrono::{Duration, Utc};
-use entity::t_refresh_token;
+use entity::{
+    sea_orm_active_enums::{AuditAction, AuditEntityType},
+    t_refresh_token, t_users,
+};
+use once_cell::sync::Lazy;
+use redis::Script;
 use sea_orm::{ActiveValue::Set, IntoActiveModel};
+use serde_json::json;
 use uuid::Uuid;
 
+// Access tokens deny-listed by jti until they expire
+const ACCESS_TOKEN_DENYLIST_PREFIX: &str = "auth:denylist:";
+// Access tokens of a user issued before this timestamp (ms) are rejected (logout-all)
+const ACCESS_TOKEN_REVOKED_BEFORE_PREFIX: &str = "auth:revoked_before:";
+// Failed sign-ins, per "email:<email>" and "ip:<ip>"
+const SIGNIN_FAILURES_PREFIX: &str = "auth:signin_failures:";
+// Present while sign-ins of an email or IP are held back, expires with the backoff or lockout
+const SIGNIN_BLOCKED_PREFIX: &str = "auth:signin_blocked:";
+// Action tokens (email verification, password reset) by SHA-256 of the token, deleted when the
+// token is used
+const ACTION_TOKEN_PREFIX: &str = "auth:action_token:";
+
+// Verified when the email is unknown, so both cases take as long as a real check
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| hash_password("dummy-password"));
+
+// Counts a failure and (re)starts the lockout window in one step, so a counter can't be left
+// without an expiry. Returns the failures in a row.
+static SIGNIN_FAILURE: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        local failures = redis.call('INCR', KEYS[1])
+        redis.call('EXPIRE', KEYS[1], ARGV[1])
+        return failures
+        ",
+    )
+});
+
+// Whether the token was issued before the revocation at `revoked_before_ms`. Milliseconds, so a
+// token refreshed right after a revocation is accepted.
+fn is_issued_before(claims: &JwtClaims, revoked_before_ms: i64) -> bool {
+    claims.issued_at_ms() <= revoked_before_ms
+}
+
+// Seconds sign-ins stay blocked after the `failures`th failure in a row: doubling from
+// `base_secs`, and the full lockout once `max_failures` is reached
+fn signin_backoff_secs(failures: i64, max_failures: i64, base_secs: u64, lockout_secs: u64) -> u64 {
+    if failures >= max_failures {
+        return lockout_secs;
+    }
+
+    let exponent = failures.saturating_sub(1).clamp(0, 32) as u32;
+    base_secs
+        .saturating_mul(2u64.saturating_pow(exponent))
+        .min(lockout_secs)
+}
+
+fn parse_refresh_token(refresh_token: &str) -> Result<Uuid, Error> {
+    Uuid::parse_str(refresh_token)
+        .map_err(|_| Error::BadRequest("Invalid refresh token".to_string()))
+}
+
 #[derive(Clone)]
 pub struct AuthService {
     pub user_repository: UserRepository,
     pub refresh_token_repository: RefreshTokenRepository,
+    pub audit_service: AuditService,
+    pub notification_service: NotificationService,
+    pub mail_service: MailService,
+    pub redis_dao: RedisDao,
 }
 
 impl AuthService {
     pub fn new(
         user_repository: UserRepository,
         refresh_token_repository: RefreshTokenRepository,
+        audit_service: AuditService,
+        notification_service: NotificationService,
+        mail_service: MailService,
+        redis_dao: RedisDao,
     ) -> Self {
         Self {
             user_repository,
             refresh_token_repository,
+            audit_service,
+            notification_service,
+            mail_service,
+            redis_dao,
         }
     }
 
-    fn create_jwt_token(&self, user: User) -> Result<String, Error> {
-        let jwt_token = JwtClaims::new(user.id, user.email, user.name);
+    async fn create_jwt_token(&self, user: User) -> Result<String, Error> {
+        let roles = self.user_repository.get_user_roles(user.id).await?;
+        let permissions = self.user_repository.get_user_permissions(user.id).await?;
+        let jwt_token = JwtClaims::new(user.id, user.email, user.name, roles, permissions);
 
         let token = jwt_token.generate_token()?;
 
         Ok(token)
     }
 
-    async fn create_refresh_token(&self, user: User) -> Result<String, Error> {
-        let token = Uuid::new_v4();
-        let refresh_token_model = t_refresh_token::ActiveModel {
-            user_id: Set(user.id),
-            token: Set(token),
+    fn build_refresh_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        parent_id: Option<i32>,
+    ) -> t_refresh_token::ActiveModel {
+        t_refresh_token::ActiveModel {
+            user_id: Set(user_id),
+            token: Set(Uuid::new_v4()),
             expired_at: Set((Utc::now()
                 + Duration::hours(*config::REFRESH_TOKEN_EXPIRATION_HOURS))
             .into()),
-            data: Set(Some(serde_json::to_value(&user)?)),
+            family_id: Set(family_id),
+            parent_id: Set(parent_id),
             ..Default::default()
-        };
+        }
+    }
+
+    // Every sign-in starts a new token family
+    async fn create_refresh_token(&self, user_id: Uuid) -> Result<String, Error> {
+        let refresh_token_model = self.build_refresh_token(user_id, Uuid::new_v4(), None);
 
         let refresh_token = self
             .refresh_token_repository
@@ -66,6 +159,349 @@ impl AuthService {
         Ok(refresh_token.token.to_string())
     }
 
+    // Revoke every token of a family and return the error to hand back to the client
+    async fn revoke_refresh_token_family(&self, family_id: Uuid) -> Error {
+        match self.refresh_token_repository.revoke_family(family_id).await {
+            Ok(revoked) => {
+                log::warn!(
+                    "refresh_token -> reuse detected, revoked {} token(s) of family {}",
+                    revoked,
+                    family_id
+                );
+                Error::UnauthorizedWithMessage(
+                    "Refresh token reuse detected, please sign in again".to_string(),
+                )
+            }
+            Err(error) => error,
+        }
+    }
+
+    async fn deny_access_token(&self, claims: &JwtClaims) -> Result<(), Error> {
+        let ttl = (claims.exp as i64 - Utc::now().timestamp()).max(1) as u64;
+
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .set_value_with_expiry(
+                &format!("{}{}", ACCESS_TOKEN_DENYLIST_PREFIX, claims.jti),
+                &Value::Boolean(true),
+                ttl,
+            )
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+
+    // Reject every access token of the user issued up to now
+    async fn revoke_issued_access_tokens(&self, user_id: Uuid) -> Result<(), Error> {
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .set_value_with_expiry(
+                &format!("{}{}", ACCESS_TOKEN_REVOKED_BEFORE_PREFIX, user_id),
+                &Value::Number(Utc::now().timestamp_millis() as f64),
+                (*config::ACCESS_TOKEN_EXPIRATION_HOURS * 3600) as u64,
+            )
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+
+    // Refuses the sign-in while the email or the IP is backing off or locked. The same answer is
+    // given for unknown emails, so it doesn't tell which accounts exist.
+    async fn check_signin_allowed(&self, subjects: &[String]) -> Result<(), Error> {
+        let mut redis_dao = self.redis_dao.clone();
+
+        for subject in subjects {
+            let ttl = redis_dao
+                .ttl(&format!("{}{}", SIGNIN_BLOCKED_PREFIX, subject))
+                .await
+                .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+            if ttl > 0 {
+                log::warn!("authenticate -> sign-in blocked for {}s: {}", ttl, subject);
+                return Err(Error::TooManyRequestsWithMessage {
+                    message: "Too many failed sign-in attempts, please try again later".to_string(),
+                    retry_after: ttl as u64,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns how many failures in a row `subject` has now, and blocks it for the backoff.
+    // A `backoff_base_secs` of 0 only blocks once `max_failures` is reached.
+    async fn record_signin_failure(
+        &self,
+        subject: &str,
+        max_failures: i64,
+        backoff_base_secs: u64,
+    ) -> Result<i64, Error> {
+        let mut redis_dao = self.redis_dao.clone();
+        let failures_key = format!("{}{}", SIGNIN_FAILURES_PREFIX, subject);
+        let lockout_secs = *config::SIGNIN_LOCKOUT_SECS;
+
+        let failures = redis_dao
+            .eval_script(
+                &SIGNIN_FAILURE,
+                &[&failures_key],
+                &[&lockout_secs.to_string()],
+            )
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        let backoff_secs =
+            signin_backoff_secs(failures, max_failures, backoff_base_secs, lockout_secs);
+        if backoff_secs > 0 {
+            redis_dao
+                .set_value_with_expiry(
+                    &format!("{}{}", SIGNIN_BLOCKED_PREFIX, subject),
+                    &Value::Number(failures as f64),
+                    backoff_secs,
+                )
+                .await
+                .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        }
+
+        Ok(failures)
+    }
+
+    // IPs are only locked at their (higher) threshold, many users can share one IP
+    async fn record_signin_failures(
+        &self,
+        email: &str,
+        ip: &str,
+        user: Option<&t_users::Model>,
+    ) -> Result<(), Error> {
+        let max_failures = *config::SIGNIN_MAX_FAILED_ATTEMPTS;
+        let failures = self
+            .record_signin_failure(
+                &format!("email:{}", email),
+                max_failures,
+                *config::SIGNIN_BACKOFF_BASE_SECS,
+            )
+            .await?;
+
+        let max_ip_failures = *config::SIGNIN_MAX_FAILED_ATTEMPTS_PER_IP;
+        let ip_failures = self
+            .record_signin_failure(&format!("ip:{}", ip), max_ip_failures, 0)
+            .await?;
+        if ip_failures >= max_ip_failures {
+            log::warn!(
+                "authenticate -> ip locked after {} failures: {}",
+                ip_failures,
+                ip
+            );
+        }
+
+        if failures != max_failures {
+            return Ok(());
+        }
+
+        log::warn!(
+            "authenticate -> email locked after {} failures: {}",
+            failures,
+            email
+        );
+        if let Some(user) = user {
+            let notification = Notification::AccountLocked {
+                user_id: user.id,
+                email: user.email.clone(),
+                locked_until: Utc::now() + Duration::seconds(*config::SIGNIN_LOCKOUT_SECS as i64),
+            };
+
+            if let Err(e) = self.notification_service.enqueue(&notification).await {
+                log::error!("failed to notify user {} of the lockout: {:?}", user.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn clear_signin_failures(&self, email: &str) -> Result<(), Error> {
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .del(&format!("{}email:{}", SIGNIN_FAILURES_PREFIX, email))
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+
+    async fn issue_action_token(
+        &self,
+        user: &t_users::Model,
+        purpose: TokenPurpose,
+        expires_in: Duration,
+    ) -> Result<String, Error> {
+        let claims = ActionTokenClaims::new(user.id, user.email.clone(), purpose, expires_in);
+        let token = claims.generate_token()?;
+
+        // Remembers the password the token was issued for, see consume_action_token
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .set_value_with_expiry(
+                &format!("{}{}", ACTION_TOKEN_PREFIX, hash_token(&token)),
+                &Value::String(hash_token(&user.password)),
+                expires_in.num_seconds().max(1) as u64,
+            )
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        Ok(token)
+    }
+
+    // Returns the user of a valid token and makes sure it can't be used again. Tokens sent to an
+    // address the user no longer has are rejected, and so are reset links issued before the
+    // password last changed: the first reset voids the other links.
+    async fn consume_action_token(
+        &self,
+        token: &str,
+        purpose: TokenPurpose,
+    ) -> Result<t_users::Model, Error> {
+        let invalid_token = || Error::BadRequest("Invalid or expired token".to_string());
+        let claims = verify_action_token(token, purpose)?;
+
+        let key = format!("{}{}", ACTION_TOKEN_PREFIX, hash_token(token));
+        let mut redis_dao = self.redis_dao.clone();
+        let issued_for = redis_dao
+            .get_value(&key)
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        let is_unused = redis_dao
+            .take(&key)
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        if !is_unused {
+            log::warn!("action token already used or revoked: {}", claims.jti);
+            return Err(invalid_token());
+        }
+
+        let user = match self.user_repository.get_user_by_id(claims.sub).await? {
+            Some(user) if user.email == claims.email && user.disabled_at.is_none() => user,
+            _ => return Err(invalid_token()),
+        };
+
+        let is_current_password = matches!(
+            issued_for,
+            Some(Value::String(password)) if password == hash_token(&user.password)
+        );
+        if purpose == TokenPurpose::PasswordReset && !is_current_password {
+            log::warn!(
+                "password changed since the reset token was issued: {}",
+                claims.jti
+            );
+            return Err(invalid_token());
+        }
+
+        Ok(user)
+    }
+
+    async fn send_email_verification(&self, user: &t_users::Model) -> Result<(), Error> {
+        let token = self
+            .issue_action_token(
+                user,
+                TokenPurpose::EmailVerification,
+                Duration::hours(*config::EMAIL_VERIFICATION_TOKEN_EXPIRATION_HOURS),
+            )
+            .await?;
+
+        self.mail_service
+            .send_email_verification(&user.email, &user.name, &token)
+            .await
+    }
+
+    async fn send_password_reset(&self, user: &t_users::Model) -> Result<(), Error> {
+        let token = self
+            .issue_action_token(
+                user,
+                TokenPurpose::PasswordReset,
+                Duration::minutes(*config::PASSWORD_RESET_TOKEN_EXPIRATION_MINUTES),
+            )
+            .await?;
+
+        self.mail_service
+            .send_password_reset(&user.email, &user.name, &token)
+            .await
+    }
+
+    // Long-lived connections re-check what auth_middleware checked when they were opened
+    #[tracing::instrument(skip(self))]
+    pub async fn is_session_active(&self, claims: &JwtClaims) -> Result<bool, Error> {
+        if self.is_access_token_revoked(claims).await? {
+            return Ok(false);
+        }
+
+        let user = self.user_repository.get_user_by_id(claims.sub).await?;
+        Ok(user.is_some_and(|user| user.disabled_at.is_none()))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn is_access_token_revoked(&self, claims: &JwtClaims) -> Result<bool, Error> {
+        let mut redis_dao = self.redis_dao.clone();
+
+        let is_denied = redis_dao
+            .exists(&format!("{}{}", ACCESS_TOKEN_DENYLIST_PREFIX, claims.jti))
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        if is_denied {
+            return Ok(true);
+        }
+
+        let revoked_before = redis_dao
+            .get_value(&format!(
+                "{}{}",
+                ACCESS_TOKEN_REVOKED_BEFORE_PREFIX, claims.sub
+            ))
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        let is_revoked = match revoked_before {
+            Some(Value::Number(revoked_before)) => is_issued_before(claims, revoked_before as i64),
+            _ => false,
+        };
+
+        Ok(is_revoked)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn logout(
+        &self,
+        claims: JwtClaims,
+        refresh_token: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let refresh_token = parse_refresh_token(&refresh_token)?;
+
+        // Only revoke the session if the refresh token belongs to the caller
+        if let Some(refresh_token_model) = self
+            .refresh_token_repository
+            .find_by_token(refresh_token)
+            .await?
+            && refresh_token_model.user_id == claims.sub
+        {
+            self.refresh_token_repository
+                .revoke_family(refresh_token_model.family_id)
+                .await?;
+        }
+
+        self.deny_access_token(&claims).await?;
+
+        Ok(CommonResponse {
+            message: "Logged out successfully".to_string(),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn logout_all(&self, claims: JwtClaims) -> Result<CommonResponse<String>, Error> {
+        let revoked = self
+            .refresh_token_repository
+            .revoke_by_user(claims.sub)
+            .await?;
+
+        self.revoke_issued_access_tokens(claims.sub).await?;
+        self.deny_access_token(&claims).await?;
+
+        Ok(CommonResponse {
+            message: format!("Logged out from {} session(s)", revoked),
+        })
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn me(&self, user_id: Uuid) -> Result<MeResponse, Error> {
         let user = self
@@ -78,29 +514,69 @@ impl AuthService {
     }
 
     #[tracing::instrument(skip(self))]
-    pub async fn authenticate(&self, body: SignInRequest) -> Result<SignInResponse, Error> {
-        let user = self
-            .user_repository
-            .get_user_by_email(&body.email)
-            .await?
-            .ok_or_else(|| {
-                log::warn!("authenticate -> user not found with email: {}", body.email);
-                Error::UnauthorizedWithMessage("Wrong email or password".to_string())
-            })?;
+    pub async fn authenticate(
+        &self,
+        body: SignInRequest,
+        ip: String,
+    ) -> Result<SignInResponse, Error> {
+        let email = body.email.trim().to_lowercase();
+        self.check_signin_allowed(&[format!("email:{}", email), format!("ip:{}", ip)])
+            .await?;
 
-        let is_password_valid = verify_password(&body.password, &user.password);
+        let user = self.user_repository.get_user_by_email(&email).await?;
 
-        if !is_password_valid {
-            log::warn!("authenticate -> invalid password for user: {}", body.email);
+        let is_password_valid = match &user {
+            Some(user) => verify_password(&body.password, &user.password),
+            None => {
+                log::warn!("authenticate -> user not found with email: {}", email);
+                verify_password(&body.password, &DUMMY_PASSWORD_HASH);
+                false
+            }
+        };
+
+        let user = match user {
+            Some(user) if is_password_valid => user,
+            user => {
+                log::warn!("authenticate -> failed sign-in for email: {}", email);
+                self.record_signin_failures(&email, &ip, user.as_ref())
+                    .await?;
+                return Err(Error::UnauthorizedWithMessage(
+                    "Wrong email or password".to_string(),
+                ));
+            }
+        };
+
+        if user.disabled_at.is_some() {
+            log::warn!("authenticate -> user is disabled: {}", email);
             return Err(Error::UnauthorizedWithMessage(
-                "Wrong email or password".to_string(),
+                "Account is disabled".to_string(),
+            ));
+        }
+
+        self.clear_signin_failures(&email).await?;
+
+        if *config::EMAIL_VERIFICATION_REQUIRED && user.email_verified_at.is_none() {
+            log::warn!("authenticate -> email is not verified: {}", body.email);
+            return Err(Error::ForbiddenWithMessage(
+                "Email address is not verified".to_string(),
             ));
         }
 
         let user_converted: User = user.into();
-        let access_token = self.create_jwt_token(user_converted.clone())?;
+        let access_token = self.create_jwt_token(user_converted.clone()).await?;
+
+        let refresh_token = self.create_refresh_token(user_converted.id).await?;
 
-        let refresh_token = self.create_refresh_token(user_converted.clone()).await?;
+        self.audit_service
+            .record_user(
+                user_converted.id,
+                AuditAction::SignIn,
+                Some(&user_converted),
+                Some(&user_converted),
+                false,
+                &ip,
+            )
+            .await;
 
         Ok(SignInResponse {
             access_token,
@@ -114,26 +590,59 @@ impl AuthService {
         &self,
         user_id: Uuid,
         body: UpdateUserRequest,
+        ip: String,
     ) -> Result<UpdateUserResponse, Error> {
-        let mut user = self
+        let before = self
             .user_repository
             .get_user_by_id(user_id)
             .await?
-            .ok_or_else(|| Error::Unauthorized)?
-            .into_active_model();
+            .ok_or_else(|| Error::Unauthorized)?;
+        let mut user = before.clone().into_active_model();
 
         if let Some(name) = body.name {
             user.name = Set(name);
         }
 
+        let password_changed = body.password.is_some();
         if let Some(password) = body.password {
             user.password = Set(hash_password(&password));
         }
 
         user.updated_at = Set(Utc::now().into());
-        let updated_user = self.user_repository.update_user(user).await?;
+        let updated_user: User = self.user_repository.update_user(user).await?.into();
 
-        Ok(UpdateUserResponse(updated_user.into()))
+        self.audit_service
+            .record_user(
+                user_id,
+                AuditAction::Update,
+                Some(&before.into()),
+                Some(&updated_user),
+                password_changed,
+                &ip,
+            )
+            .await;
+
+        Ok(UpdateUserResponse(updated_user))
+    }
+
+    // Merge patch of the current user, nulls are rejected by validate_user_patch
+    #[tracing::instrument(skip(self))]
+    pub async fn patch(
+        &self,
+        user_id: Uuid,
+        body: PatchUserRequest,
+        ip: String,
+    ) -> Result<UpdateUserResponse, Error> {
+        self.update(user_id, body.into(), ip).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_activity(
+        &self,
+        user_id: Uuid,
+        params: GetAuditLogRequest,
+    ) -> Result<GetAuditLogResponse, Error> {
+        self.audit_service.get_actor_activity(user_id, params).await
     }
 
     #[tracing::instrument(skip(self))]
@@ -149,38 +658,473 @@ impl AuthService {
 
         let user = self.user_repository.create_user(user_model).await?;
 
+        // Sent in the background, the account is created either way and the mail can be sent
+        // again with resend_verification
+        let service = self.clone();
+        let created_user = user.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.send_email_verification(&created_user).await {
+                log::error!(
+                    "failed to send verification email to {}: {:?}",
+                    created_user.email,
+                    e
+                );
+            }
+        });
+
         let user_converted: User = user.into();
-        let access_token = self.create_jwt_token(user_converted.clone())?;
+        if *config::EMAIL_VERIFICATION_REQUIRED {
+            return Ok(SignUpResponse {
+                access_token: None,
+                refresh_token: None,
+                user: user_converted,
+            });
+        }
+
+        let access_token = self.create_jwt_token(user_converted.clone()).await?;
 
-        let refresh_token = self.create_refresh_token(user_converted.clone()).await?;
+        let refresh_token = self.create_refresh_token(user_converted.id).await?;
 
         Ok(SignUpResponse {
-            access_token,
-            refresh_token,
+            access_token: Some(access_token),
+            refresh_token: Some(refresh_token),
             user: user_converted,
         })
     }
 
     #[tracing::instrument(skip(self))]
-    pub async fn refresh_token(&self, refresh_token: Uuid) -> Result<RefreshTokenResponse, Error> {
+    pub async fn verify_email(
+        &self,
+        body: VerifyEmailRequest,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let before = self
+            .consume_action_token(&body.token, TokenPurpose::EmailVerification)
+            .await?;
+
+        if before.email_verified_at.is_none() {
+            let mut user = before.clone().into_active_model();
+            user.email_verified_at = Set(Some(Utc::now().into()));
+            user.updated_at = Set(Utc::now().into());
+            let updated_user: User = self.user_repository.update_user(user).await?.into();
+
+            self.audit_service
+                .record_user(
+                    updated_user.id,
+                    AuditAction::Update,
+                    Some(&before.into()),
+                    Some(&updated_user),
+                    false,
+                    &ip,
+                )
+                .await;
+        }
+
+        Ok(CommonResponse {
+            message: "Email address verified".to_string(),
+        })
+    }
+
+    // Answers the same whether the email exists or not, so it can't be used to find accounts
+    #[tracing::instrument(skip(self))]
+    pub async fn resend_verification(
+        &self,
+        body: ResendVerificationRequest,
+    ) -> Result<CommonResponse<String>, Error> {
+        let user = self.user_repository.get_user_by_email(&body.email).await?;
+
+        match user {
+            Some(user) if user.email_verified_at.is_none() && user.disabled_at.is_none() => {
+                // Sent in the background, answering only after the mail would give away that
+                // the account exists
+                let service = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = service.send_email_verification(&user).await {
+                        log::error!(
+                            "failed to send verification email to {}: {:?}",
+                            user.email,
+                            e
+                        );
+                    }
+                });
+            }
+            _ => log::warn!("resend_verification -> nothing to send for: {}", body.email),
+        }
+
+        Ok(CommonResponse {
+            message: "If the email address needs verifying, a new link has been sent".to_string(),
+        })
+    }
+
+    // Same answer, and as fast, whether the email exists or not, like resend_verification
+    #[tracing::instrument(skip(self))]
+    pub async fn forgot_password(
+        &self,
+        body: ForgotPasswordRequest,
+    ) -> Result<CommonResponse<String>, Error> {
+        match self.user_repository.get_user_by_email(&body.email).await? {
+            Some(user) if user.disabled_at.is_none() => {
+                let service = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = service.send_password_reset(&user).await {
+                        log::error!(
+                            "failed to send password reset email to {}: {:?}",
+                            user.email,
+                            e
+                        );
+                    }
+                });
+            }
+            _ => log::warn!("forgot_password -> nothing to send for: {}", body.email),
+        }
+
+        Ok(CommonResponse {
+            message: "If an account exists for this email address, a reset link has been sent"
+                .to_string(),
+        })
+    }
+
+    // Signs the user out everywhere, whoever knew the old password loses access too
+    #[tracing::instrument(skip(self))]
+    pub async fn reset_password(
+        &self,
+        body: ResetPasswordRequest,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let before = self
+            .consume_action_token(&body.token, TokenPurpose::PasswordReset)
+            .await?;
+
+        let mut user = before.clone().into_active_model();
+        user.password = Set(hash_password(&body.password));
+        // The link was opened from the mailbox, which proves the address too
+        if before.email_verified_at.is_none() {
+            user.email_verified_at = Set(Some(Utc::now().into()));
+        }
+        user.updated_at = Set(Utc::now().into());
+        let updated_user: User = self.user_repository.update_user(user).await?.into();
+
+        let revoked = self
+            .refresh_token_repository
+            .revoke_by_user(updated_user.id)
+            .await?;
+        self.revoke_issued_access_tokens(updated_user.id).await?;
+        self.clear_signin_failures(&updated_user.email.to_lowercase())
+            .await?;
+        log::info!(
+            "reset_password -> password reset, revoked {} session(s) of user {}",
+            revoked,
+            updated_user.id
+        );
+
+        self.audit_service
+            .record_user(
+                updated_user.id,
+                AuditAction::Update,
+                Some(&before.into()),
+                Some(&updated_user),
+                true,
+                &ip,
+            )
+            .await;
+
+        Ok(CommonResponse {
+            message: "Password has been reset, please sign in again".to_string(),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn refresh_token(
+        &self,
+        refresh_token: String,
+    ) -> Result<RefreshTokenResponse, Error> {
+        let refresh_token = parse_refresh_token(&refresh_token)?;
         let refresh_token_model = self
             .refresh_token_repository
-            .get_refresh_token(refresh_token)
+            .find_by_token(refresh_token)
             .await?
             .ok_or_else(|| Error::BadRequest("Invalid refresh token".to_string()))?;
 
-        let refresh_token_data = refresh_token_model
-            .data
-            .ok_or_else(|| Error::BadRequest("Invalid refresh token".to_string()))?;
+        // A rotated token presented again means it leaked, kill the whole family
+        if refresh_token_model.revoked_at.is_some() {
+            return Err(self
+                .revoke_refresh_token_family(refresh_token_model.family_id)
+                .await);
+        }
 
-        let user = serde_json::from_value::<User>(refresh_token_data)?;
+        if refresh_token_model.expired_at <= Utc::now() {
+            return Err(Error::BadRequest("Invalid refresh token".to_string()));
+        }
 
-        let user_converted: User = user.into();
-        let access_token = self.create_jwt_token(user_converted)?;
+        // Mint claims from the current user row, not from what it looked like at sign-in
+        let user = self
+            .user_repository
+            .get_user_by_id(refresh_token_model.user_id)
+            .await?
+            .ok_or_else(|| {
+                log::warn!(
+                    "refresh_token -> user not found: {}",
+                    refresh_token_model.user_id
+                );
+                Error::Unauthorized
+            })?;
+
+        if user.disabled_at.is_some() {
+            log::warn!("refresh_token -> user is disabled: {}", user.id);
+            return Err(Error::UnauthorizedWithMessage(
+                "Account is disabled".to_string(),
+            ));
+        }
+
+        let next_refresh_token_model = self.build_refresh_token(
+            refresh_token_model.user_id,
+            refresh_token_model.family_id,
+            Some(refresh_token_model.id),
+        );
+
+        let next_refresh_token = match self
+            .refresh_token_repository
+            .rotate_refresh_token(refresh_token_model.id, next_refresh_token_model)
+            .await?
+        {
+            Some(next_refresh_token) => next_refresh_token,
+            // Another request rotated this token first
+            None => {
+                return Err(self
+                    .revoke_refresh_token_family(refresh_token_model.family_id)
+                    .await);
+            }
+        };
+
+        let access_token = self.create_jwt_token(user.into()).await?;
 
         Ok(RefreshTokenResponse {
             access_token,
-            refresh_token: refresh_token.to_string(),
+            refresh_token: next_refresh_token.token.to_string(),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all_users(
+        &self,
+        params: GetAllUsersRequest,
+    ) -> Result<GetAllUsersResponse, Error> {
+        let limit = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
+        let page = params.page.unwrap_or(*config::DEFAULT_PAGE);
+
+        let (users, total) = self
+            .user_repository
+            .get_all_users(limit, page.saturating_sub(1))
+            .await?;
+
+        let mut roles_by_user = self
+            .user_repository
+            .get_roles_by_user_ids(users.iter().map(|user| user.id).collect())
+            .await?;
+
+        let data = users
+            .into_iter()
+            .map(|user| AdminUser {
+                roles: roles_by_user.remove(&user.id).unwrap_or_default(),
+                disabled_at: user.disabled_at.map(|d| d.with_timezone(&Utc)),
+                user: user.into(),
+            })
+            .collect();
+
+        Ok(GetAllUsersResponse {
+            total,
+            total_pages: total.div_ceil(limit),
+            data,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_user_disabled(
+        &self,
+        id: String,
+        disabled: bool,
+        admin_id: Uuid,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id =
+            Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid user id".to_string()))?;
+
+        if id == admin_id {
+            return Err(Error::BadRequest(
+                "You cannot change the status of your own account".to_string(),
+            ));
+        }
+
+        let before = self
+            .user_repository
+            .get_user_by_id(id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("User with id {} not found", id)))?;
+        // `User` doesn't carry the disabled date, so only that field is diffed
+        let disabled_at = |user: &t_users::Model| json!({ "disabledAt": user.disabled_at.map(|at| at.timestamp_millis()) });
+        let disabled_at_before = disabled_at(&before);
+
+        let mut user = before.into_active_model();
+        user.disabled_at = Set(disabled.then(|| Utc::now().into()));
+        user.updated_at = Set(Utc::now().into());
+        let updated_user = self.user_repository.update_user(user).await?;
+
+        let changes =
+            AuditService::diff(Some(&disabled_at_before), Some(&disabled_at(&updated_user)));
+        if changes.is_some() {
+            self.audit_service
+                .record(
+                    admin_id,
+                    AuditEntityType::User,
+                    id,
+                    AuditAction::Update,
+                    changes,
+                    &ip,
+                )
+                .await;
+        }
+
+        // A disabled account must lose its sessions right away
+        if disabled {
+            self.refresh_token_repository.revoke_by_user(id).await?;
+            self.revoke_issued_access_tokens(id).await?;
+        }
+
+        Ok(CommonResponse {
+            message: format!(
+                "{} user id {} successfully",
+                if disabled { "Disabled" } else { "Enabled" },
+                id
+            ),
         })
     }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_roles(&self) -> Result<GetRolesResponse, Error> {
+        let roles = self
+            .user_repository
+            .get_roles()
+            .await?
+            .into_iter()
+            .map(|(role, permissions)| RoleResponse {
+                name: role.name,
+                permissions: permissions
+                    .into_iter()
+                    .map(|permission| permission.name)
+                    .collect(),
+            })
+            .collect();
+
+        Ok(GetRolesResponse(roles))
+    }
+
+    // Roles travel in the access token, so the user's tokens are revoked to apply the change
+    // right away. The client gets the new roles with its next refresh.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_user_role(
+        &self,
+        id: String,
+        role: String,
+        granted: bool,
+        admin_id: Uuid,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id =
+            Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid user id".to_string()))?;
+
+        if id == admin_id && !granted {
+            return Err(Error::BadRequest(
+                "You cannot remove roles from your own account".to_string(),
+            ));
+        }
+
+        self.user_repository
+            .get_user_by_id(id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("User with id {} not found", id)))?;
+        let role = self
+            .user_repository
+            .find_role_by_name(&role)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Role {} not found", role)))?;
+
+        let roles_before = self.user_repository.get_user_roles(id).await?;
+        let changed = if granted {
+            self.user_repository.add_user_role(id, role.id).await?
+        } else {
+            self.user_repository.remove_user_role(id, role.id).await?
+        };
+
+        if changed {
+            self.revoke_issued_access_tokens(id).await?;
+
+            let roles_after = self.user_repository.get_user_roles(id).await?;
+            self.audit_service
+                .record(
+                    admin_id,
+                    AuditEntityType::User,
+                    id,
+                    AuditAction::Update,
+                    AuditService::diff(
+                        Some(&json!({ "roles": roles_before })),
+                        Some(&json!({ "roles": roles_after })),
+                    ),
+                    &ip,
+                )
+                .await;
+        }
+
+        Ok(CommonResponse {
+            message: format!(
+                "{} role {} {} user id {} successfully",
+                if granted { "Granted" } else { "Removed" },
+                role.name,
+                if granted { "to" } else { "from" },
+                id
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signin_backoff_doubles_until_lockout() {
+        let backoff = |failures| signin_backoff_secs(failures, 5, 1, 900);
+
+        assert_eq!((1..=4).map(backoff).collect::<Vec<_>>(), vec![1, 2, 4, 8]);
+        assert_eq!(backoff(5), 900);
+        assert_eq!(backoff(6), 900);
+        // Never longer than the lockout itself
+        assert_eq!(signin_backoff_secs(40, 50, 1, 900), 900);
+        // Without a base only the lockout blocks
+        assert_eq!(signin_backoff_secs(19, 20, 0, 900), 0);
+        assert_eq!(signin_backoff_secs(20, 20, 0, 900), 900);
+    }
+
+    #[test]
+    fn test_token_issued_right_after_revocation_stays_valid() {
+        let claims = || {
+            JwtClaims::new(
+                Uuid::new_v4(),
+                "user@example.com".to_string(),
+                "User".to_string(),
+                vec![],
+                vec![],
+            )
+        };
+        let before = claims();
+        let revoked_before_ms = before.iat_ms;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let after = claims();
+
+        assert!(is_issued_before(&before, revoked_before_ms));
+        assert!(!is_issued_before(&after, revoked_before_ms));
+
+        // Without iat_ms the whole second counts as revoked
+        let legacy = JwtClaims { iat_ms: 0, ..after };
+        assert!(is_issued_before(&legacy, legacy.iat as i64 * 1000));
+    }
 }
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use crate::{config, daos::redis_dao::RedisDao, models::errors::Error};
+use entity::t_todos;
+use redis::{PushInfo, PushKind};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc::UnboundedReceiver};
+use uuid::Uuid;
+
+// Events a single stream can fall behind by before it is told to resync
+const EVENT_BUFFER_SIZE: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoEventType {
+    Created,
+    Updated,
+    Deleted,
+}
+
+// What the clients receive
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TodoEvent {
+    #[serde(rename = "type")]
+    pub event_type: TodoEventType,
+    pub todo: t_todos::Model,
+}
+
+// What goes over the Redis channel, the publishing instance resolves who may see the todo
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TodoEventMessage {
+    #[serde(rename = "userIds")]
+    pub user_ids: Vec<Uuid>,
+    pub event: TodoEvent,
+}
+
+#[derive(Clone)]
+pub struct EventService {
+    pub redis_dao: RedisDao,
+    pub sender: broadcast::Sender<Arc<TodoEventMessage>>,
+}
+
+impl EventService {
+    // `pushes` receives the pub/sub messages of the connection behind `redis_dao`
+    pub fn new(redis_dao: RedisDao, pushes: UnboundedReceiver<PushInfo>) -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        tokio::spawn(Self::forward(pushes, sender.clone()));
+
+        Self { redis_dao, sender }
+    }
+
+    pub async fn subscribe_channel(&self) -> Result<(), Error> {
+        let mut connection = self.redis_dao.connection.clone();
+        connection
+            .subscribe(config::TODO_EVENTS_CHANNEL.as_str())
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+
+    pub async fn publish(&self, message: &TodoEventMessage) -> Result<(), Error> {
+        let payload = serde_json::to_string(message)?;
+
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .publish(config::TODO_EVENTS_CHANNEL.as_str(), &payload)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<TodoEventMessage>> {
+        self.sender.subscribe()
+    }
+
+    // Hands every message of the channel to the local streams, including the ones this
+    // instance published itself
+    async fn forward(
+        mut pushes: UnboundedReceiver<PushInfo>,
+        sender: broadcast::Sender<Arc<TodoEventMessage>>,
+    ) {
+        while let Some(push) = pushes.recv().await {
+            let Some(payload) = Self::message_payload(push) else {
+                continue;
+            };
+
+            match serde_json::from_str::<TodoEventMessage>(&payload) {
+                // Fails only while no stream is open
+                Ok(message) => _ = sender.send(Arc::new(message)),
+                Err(e) => log::error!("invalid todo event message: {:?}", e),
+            }
+        }
+    }
+
+    fn message_payload(push: PushInfo) -> Option<String> {
+        if push.kind != PushKind::Message {
+            return None;
+        }
+
+        // [channel, payload]
+        let [channel, payload] = <[redis::Value; 2]>::try_from(push.data).ok()?;
+        let channel: String = redis::from_owned_redis_value(channel).ok()?;
+
+        (channel == *config::TODO_EVENTS_CHANNEL)
+            .then(|| redis::from_owned_redis_value(payload).ok())
+            .flatten()
+    }
+}
@@ -96,6 +96,7 @@ pub struct RequestOptions {
     timeout: Option<Duration>,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for RequestOptions {
     fn default() -> Self {
         Self {
@@ -140,6 +141,7 @@ impl HttpRequestService {
     }
 
     // Create a new HTTP request service with custom configuration
+    #[allow(clippy::useless_conversion)]
     pub fn with_config(config: HttpRequestConfig) -> Result<Self, HttpRequestError> {
         let client = Client::builder()
             .timeout(config.timeout)
@@ -211,6 +213,7 @@ impl HttpRequestService {
     }
 
     // Generate curl command for debugging
+    #[allow(clippy::collapsible_if)]
     fn log_curl_command(&self, request_id: &str, req: &RequestBuilder) {
         if let Some(cloned_req) = req.try_clone() {
             if let Ok(request) = cloned_req.build() {
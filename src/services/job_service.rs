@@ -1,17 +1,33 @@
 use std::sync::Arc;
 
+use chrono::{Duration, Utc};
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
 
+use crate::{
+    config,
+    repositories::todo_repository::TodoRepository,
+    services::notification_service::{Notification, NotificationService},
+};
+
 pub struct JobService {
     scheduler: Arc<Mutex<JobScheduler>>,
+    todo_repository: TodoRepository,
+    notification_service: NotificationService,
 }
 
 impl JobService {
-    pub async fn new() -> Result<Self, JobSchedulerError> {
+    pub async fn new(
+        todo_repository: TodoRepository,
+        notification_service: NotificationService,
+    ) -> Result<Self, JobSchedulerError> {
         let scheduler = JobScheduler::new().await?;
         let scheduler = Arc::new(Mutex::new(scheduler));
-        Ok(Self { scheduler })
+        Ok(Self {
+            scheduler,
+            todo_repository,
+            notification_service,
+        })
     }
 
     pub async fn start(&self) -> Result<(), JobSchedulerError> {
@@ -33,6 +49,9 @@ impl JobService {
 
         self.add_test_job_1(&scheduler).await?;
         self.add_test_job_2(&scheduler).await?;
+        self.add_purge_trash_job(&scheduler).await?;
+        self.add_todo_reminder_job(&scheduler).await?;
+        self.add_todo_recurrence_job(&scheduler).await?;
 
         log::info!("jobs setup completed");
         Ok(())
@@ -59,4 +78,115 @@ impl JobService {
             .await?;
         Ok(())
     }
+
+    // Permanently delete todos that stayed in the trash longer than the retention
+    async fn add_purge_trash_job(&self, scheduler: &JobScheduler) -> Result<(), JobSchedulerError> {
+        let todo_repository = self.todo_repository.clone();
+
+        scheduler
+            .add(Job::new_async(
+                config::TODO_TRASH_PURGE_CRON.as_str(),
+                move |_, _| {
+                    let todo_repository = todo_repository.clone();
+                    Box::pin(async move {
+                        let cutoff =
+                            Utc::now() - Duration::days(*config::TODO_TRASH_RETENTION_DAYS);
+
+                        match todo_repository.purge_deleted_before(cutoff).await {
+                            Ok(purged) => {
+                                log::info!("purge trash job removed {} todo(s)", purged)
+                            }
+                            Err(e) => log::error!("purge trash job failed: {:?}", e),
+                        }
+                    })
+                },
+            )?)
+            .await?;
+        Ok(())
+    }
+
+    // Queues a notification for every todo whose reminder is due
+    async fn add_todo_reminder_job(
+        &self,
+        scheduler: &JobScheduler,
+    ) -> Result<(), JobSchedulerError> {
+        let todo_repository = self.todo_repository.clone();
+        let notification_service = self.notification_service.clone();
+
+        scheduler
+            .add(Job::new_async(
+                config::TODO_REMINDER_CRON.as_str(),
+                move |_, _| {
+                    let todo_repository = todo_repository.clone();
+                    let notification_service = notification_service.clone();
+                    Box::pin(async move {
+                        let todos = match todo_repository.claim_due_reminders(Utc::now()).await {
+                            Ok(todos) => todos,
+                            Err(e) => {
+                                log::error!("todo reminder job failed: {:?}", e);
+                                return;
+                            }
+                        };
+
+                        for todo in &todos {
+                            if let Err(e) = notification_service
+                                .enqueue(&Notification::from(todo))
+                                .await
+                            {
+                                log::error!(
+                                    "failed to queue reminder of todo {}: {:?}",
+                                    todo.id,
+                                    e
+                                );
+
+                                // Retried on the next run
+                                if let Err(e) = todo_repository.release_reminder(todo.id).await {
+                                    log::error!(
+                                        "failed to release reminder of todo {}: {:?}",
+                                        todo.id,
+                                        e
+                                    );
+                                }
+                            }
+                        }
+
+                        if !todos.is_empty() {
+                            log::info!("todo reminder job queued {} reminder(s)", todos.len());
+                        }
+                    })
+                },
+            )?)
+            .await?;
+        Ok(())
+    }
+
+    // Creates the upcoming occurrences of recurring todos ahead of time
+    async fn add_todo_recurrence_job(
+        &self,
+        scheduler: &JobScheduler,
+    ) -> Result<(), JobSchedulerError> {
+        let todo_repository = self.todo_repository.clone();
+
+        scheduler
+            .add(Job::new_async(
+                config::TODO_RECURRENCE_CRON.as_str(),
+                move |_, _| {
+                    let todo_repository = todo_repository.clone();
+                    Box::pin(async move {
+                        let horizon =
+                            Utc::now() + Duration::days(*config::TODO_RECURRENCE_HORIZON_DAYS);
+
+                        match todo_repository.materialize_occurrences(horizon).await {
+                            Ok(0) => {}
+                            Ok(created) => {
+                                log::info!("todo recurrence job created {} todo(s)", created)
+                            }
+                            Err(e) => log::error!("todo recurrence job failed: {:?}", e),
+                        }
+                    })
+                },
+            )?)
+            .await?;
+        Ok(())
+    }
 }
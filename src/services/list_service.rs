@@ -0,0 +1,389 @@
+use crate::{
+    models::{
+        errors::Error,
+        request::{
+            CreateListRequest, InviteListMemberRequest, UpdateListMemberRequest, UpdateListRequest,
+        },
+        response::{CommonResponse, ListMemberResponse, ListResponse, ReceivedInvitationResponse},
+    },
+    repositories::{list_repository::ListRepository, user_repository::UserRepository},
+};
+use chrono::Utc;
+use entity::{sea_orm_active_enums::ListRole, t_list_invitations, t_list_members, t_users};
+use sea_orm::{ActiveValue::Set, IntoActiveModel, SqlErr};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ListService {
+    pub list_repository: ListRepository,
+    pub user_repository: UserRepository,
+}
+
+impl ListService {
+    pub fn new(list_repository: ListRepository, user_repository: UserRepository) -> Self {
+        Self {
+            list_repository,
+            user_repository,
+        }
+    }
+
+    fn parse_id(id: &str, kind: &str) -> Result<Uuid, Error> {
+        Uuid::parse_str(id).map_err(|_| Error::BadRequest(format!("Invalid {} id", kind)))
+    }
+
+    // Lists are reported as missing to non-members so their existence isn't leaked
+    async fn require_role(
+        &self,
+        list_id: Uuid,
+        user_id: Uuid,
+        role: ListRole,
+    ) -> Result<ListRole, Error> {
+        match self.list_repository.get_role(list_id, user_id).await? {
+            Some(current) if current >= role => Ok(current),
+            Some(_) => Err(Error::ForbiddenWithMessage(format!(
+                "{:?} access to the list is required",
+                role
+            ))),
+            None => Err(Error::NotFound(format!(
+                "List with id {} not found",
+                list_id
+            ))),
+        }
+    }
+
+    async fn current_user(&self, user_id: Uuid) -> Result<t_users::Model, Error> {
+        self.user_repository
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or(Error::Unauthorized)
+    }
+
+    // Invitations are addressed to an email, only someone who proved they own it may see them
+    async fn current_verified_user(&self, user_id: Uuid) -> Result<t_users::Model, Error> {
+        let user = self.current_user(user_id).await?;
+
+        if user.email_verified_at.is_none() {
+            return Err(Error::ForbiddenWithMessage(
+                "Verify your email address to see the invitations sent to it".to_string(),
+            ));
+        }
+
+        Ok(user)
+    }
+
+    // The invitation must have been sent to the current email of the user
+    async fn find_received_invitation(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<t_list_invitations::Model, Error> {
+        let id = Self::parse_id(&id, "invitation")?;
+        let user = self.current_verified_user(user_id).await?;
+        let not_found = || Error::NotFound(format!("Invitation with id {} not found", id));
+
+        let invitation = self
+            .list_repository
+            .find_invitation(id)
+            .await?
+            .ok_or_else(not_found)?;
+
+        if !invitation.email.eq_ignore_ascii_case(&user.email) {
+            return Err(not_found());
+        }
+
+        Ok(invitation)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_lists(&self, user_id: Uuid) -> Result<Vec<ListResponse>, Error> {
+        let lists = self.list_repository.get_lists(user_id).await?;
+
+        Ok(lists
+            .into_iter()
+            .map(|(list, member)| ListResponse {
+                list,
+                role: member.role,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_list(&self, id: String, user_id: Uuid) -> Result<ListResponse, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        let role = self.require_role(id, user_id, ListRole::Viewer).await?;
+
+        let list = self
+            .list_repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("List with id {} not found", id)))?;
+
+        Ok(ListResponse { list, role })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn create_list(
+        &self,
+        body: CreateListRequest,
+        user_id: Uuid,
+    ) -> Result<ListResponse, Error> {
+        let list = self
+            .list_repository
+            .create(body.into_active_model(), user_id)
+            .await?;
+
+        Ok(ListResponse {
+            list,
+            role: ListRole::Owner,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn update_list(
+        &self,
+        id: String,
+        body: UpdateListRequest,
+        user_id: Uuid,
+    ) -> Result<ListResponse, Error> {
+        let list = self.get_list(id, user_id).await?;
+        self.require_role(list.list.id, user_id, ListRole::Owner)
+            .await?;
+
+        let mut model = list.list.into_active_model();
+        model.name = Set(body.name.trim().to_string());
+        model.updated_at = Set(Utc::now());
+
+        Ok(ListResponse {
+            list: self.list_repository.update(model).await?,
+            role: list.role,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_list(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        self.require_role(id, user_id, ListRole::Owner).await?;
+
+        self.list_repository.delete(id).await?;
+
+        Ok(CommonResponse {
+            message: format!("Deleted list id {} successfully", id),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_members(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<Vec<ListMemberResponse>, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        self.require_role(id, user_id, ListRole::Viewer).await?;
+
+        let members = self.list_repository.get_members(id).await?;
+
+        Ok(members
+            .into_iter()
+            .map(|(member, user)| ListMemberResponse {
+                member,
+                name: user.name,
+                email: user.email,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn update_member(
+        &self,
+        id: String,
+        member_id: String,
+        body: UpdateListMemberRequest,
+        user_id: Uuid,
+    ) -> Result<t_list_members::Model, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        let member_id = Self::parse_id(&member_id, "user")?;
+        self.require_role(id, user_id, ListRole::Owner).await?;
+
+        self.list_repository
+            .change_member(id, member_id, Some(body.role))
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Member {} not found", member_id)))
+    }
+
+    // Owners can remove anyone, other members can only leave
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_member(
+        &self,
+        id: String,
+        member_id: String,
+        user_id: Uuid,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        let member_id = Self::parse_id(&member_id, "user")?;
+
+        let required = if member_id == user_id {
+            ListRole::Viewer
+        } else {
+            ListRole::Owner
+        };
+        self.require_role(id, user_id, required).await?;
+
+        self.list_repository
+            .change_member(id, member_id, None)
+            .await?;
+
+        Ok(CommonResponse {
+            message: format!("Removed member {} from list {}", member_id, id),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn invite_member(
+        &self,
+        id: String,
+        body: InviteListMemberRequest,
+        user_id: Uuid,
+    ) -> Result<t_list_invitations::Model, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        self.require_role(id, user_id, ListRole::Owner).await?;
+
+        let email = body.email.trim().to_lowercase();
+
+        if let Some(user) = self.user_repository.get_user_by_email(&email).await?
+            && self.list_repository.get_role(id, user.id).await?.is_some()
+        {
+            return Err(Error::Conflict(format!(
+                "{} is already a member of the list",
+                email
+            )));
+        }
+
+        let invitation = t_list_invitations::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            list_id: Set(id),
+            email: Set(email.clone()),
+            role: Set(body.role),
+            invited_by: Set(user_id),
+            created_at: Set(Utc::now()),
+        };
+
+        // The unique index on (list_id, LOWER(email)) reports pending invitations
+        let invitation = self
+            .list_repository
+            .create_invitation(invitation)
+            .await
+            .map_err(|e| match &e {
+                Error::Db(db_err)
+                    if matches!(db_err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+                {
+                    Error::Conflict(format!("{} has already been invited", email))
+                }
+                _ => e,
+            })?;
+
+        log::info!("invited {} to list {}", email, id);
+        Ok(invitation)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_invitations(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<Vec<t_list_invitations::Model>, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        self.require_role(id, user_id, ListRole::Owner).await?;
+
+        self.list_repository.get_invitations(id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_invitation(
+        &self,
+        id: String,
+        invitation_id: String,
+        user_id: Uuid,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id = Self::parse_id(&id, "list")?;
+        let invitation_id = Self::parse_id(&invitation_id, "invitation")?;
+        self.require_role(id, user_id, ListRole::Owner).await?;
+
+        let not_found =
+            || Error::NotFound(format!("Invitation with id {} not found", invitation_id));
+        let invitation = self
+            .list_repository
+            .find_invitation(invitation_id)
+            .await?
+            .filter(|invitation| invitation.list_id == id)
+            .ok_or_else(not_found)?;
+
+        if self
+            .list_repository
+            .delete_invitation(invitation.id)
+            .await?
+            == 0
+        {
+            return Err(not_found());
+        }
+
+        Ok(CommonResponse {
+            message: format!("Revoked invitation id {} successfully", invitation_id),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_received_invitations(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ReceivedInvitationResponse>, Error> {
+        let user = self.current_verified_user(user_id).await?;
+        let invitations = self
+            .list_repository
+            .get_invitations_by_email(&user.email)
+            .await?;
+
+        Ok(invitations
+            .into_iter()
+            .map(|(invitation, list)| ReceivedInvitationResponse {
+                invitation,
+                list_name: list.name,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn accept_invitation(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<ListResponse, Error> {
+        let invitation = self.find_received_invitation(id, user_id).await?;
+
+        self.list_repository
+            .accept_invitation(&invitation, user_id)
+            .await?;
+
+        self.get_list(invitation.list_id.to_string(), user_id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn decline_invitation(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<CommonResponse<String>, Error> {
+        let invitation = self.find_received_invitation(id, user_id).await?;
+
+        self.list_repository
+            .delete_invitation(invitation.id)
+            .await?;
+
+        Ok(CommonResponse {
+            message: format!("Declined invitation id {} successfully", invitation.id),
+        })
+    }
+}
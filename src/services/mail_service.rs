@@ -0,0 +1,420 @@
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+use tokio_native_tls::{TlsConnector, native_tls};
+use uuid::Uuid;
+
+use crate::{config, models::errors::Error};
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    // RFC 5322 message, plain text in UTF-8. Line breaks are dropped from header values so
+    // they can't inject headers.
+    pub fn to_message(&self, from: &str) -> String {
+        let header = |value: &str| value.replace(['\r', '\n'], " ");
+        let subject = if self.subject.is_ascii() {
+            header(&self.subject)
+        } else {
+            format!("=?UTF-8?B?{}?=", STANDARD.encode(header(&self.subject)))
+        };
+
+        let domain = from
+            .rsplit_once('@')
+            .map_or("localhost", |(_, domain)| domain);
+        let body = self.body.replace("\r\n", "\n").replace('\n', "\r\n");
+
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n{}\r\n",
+            header(from),
+            header(&self.to),
+            subject,
+            Utc::now().to_rfc2822(),
+            Uuid::new_v4(),
+            header(domain),
+            body
+        )
+    }
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), Error>;
+}
+
+// Local development: logs every mail, and keeps it as an .eml file when `dir` is set
+pub struct FileMailer {
+    pub from: String,
+    pub dir: Option<PathBuf>,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), Error> {
+        let message = mail.to_message(&self.from);
+        log::info!("mail to {}: {}\n{}", mail.to, mail.subject, mail.body);
+
+        if let Some(dir) = &self.dir {
+            tokio::fs::create_dir_all(dir).await?;
+
+            let path = dir.join(format!(
+                "{}-{}.eml",
+                Utc::now().timestamp_millis(),
+                Uuid::new_v4()
+            ));
+            tokio::fs::write(&path, message).await?;
+            log::info!("mail saved to {}", path.display());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpSecurity {
+    None,
+    // Plain connection upgraded with STARTTLS (usually port 587)
+    StartTls,
+    // TLS from the start (usually port 465)
+    Tls,
+}
+
+impl SmtpSecurity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "starttls" => Some(Self::StartTls),
+            "tls" => Some(Self::Tls),
+            _ => None,
+        }
+    }
+}
+
+pub struct SmtpMailer {
+    pub from: String,
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub timeout: Duration,
+}
+
+trait SmtpStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SmtpStream for T {}
+
+// A server that stops answering must not hold the caller forever
+async fn with_timeout<F: Future>(limit: Duration, step: F) -> Result<F::Output, Error> {
+    timeout(limit, step)
+        .await
+        .map_err(|_| Error::InternalServerError("SMTP server timed out".to_string()))
+}
+
+// One SMTP session, commands are sent one at a time (no pipelining)
+struct SmtpSession {
+    stream: BufReader<Box<dyn SmtpStream>>,
+    timeout: Duration,
+}
+
+impl SmtpSession {
+    // Returns the code of the reply, failing unless it is `expected`
+    async fn expect(&mut self, expected: u16) -> Result<u16, Error> {
+        let mut reply = String::new();
+
+        // Multi-line replies continue with "250-", the last line is "250 "
+        loop {
+            let mut line = String::new();
+            if with_timeout(self.timeout, self.stream.read_line(&mut line)).await?? == 0 {
+                return Err(Error::InternalServerError(
+                    "SMTP server closed the connection".to_string(),
+                ));
+            }
+            reply.push_str(&line);
+
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+
+        let code = reply.get(..3).and_then(|code| code.parse::<u16>().ok());
+        match code {
+            Some(code) if code == expected => Ok(code),
+            _ => Err(Error::InternalServerError(format!(
+                "unexpected SMTP reply, expected {}: {}",
+                expected,
+                reply.trim_end()
+            ))),
+        }
+    }
+
+    async fn command(&mut self, command: &str, expected: u16) -> Result<u16, Error> {
+        let stream = self.stream.get_mut();
+        with_timeout(self.timeout, async {
+            stream.write_all(command.as_bytes()).await?;
+            stream.write_all(b"\r\n").await?;
+            stream.flush().await
+        })
+        .await??;
+        self.expect(expected).await
+    }
+
+    fn into_stream(self) -> Box<dyn SmtpStream> {
+        self.stream.into_inner()
+    }
+}
+
+impl SmtpMailer {
+    async fn connect_tls(&self, stream: Box<dyn SmtpStream>) -> Result<Box<dyn SmtpStream>, Error> {
+        let connector = native_tls::TlsConnector::new()
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        let connector = TlsConnector::from(connector);
+        let stream = with_timeout(self.timeout, connector.connect(&self.host, stream))
+            .await?
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+        Ok(Box::new(stream))
+    }
+
+    async fn open_session(&self) -> Result<SmtpSession, Error> {
+        // Without TLS the credentials would be sent in clear text
+        if self.security == SmtpSecurity::None && self.username.is_some() {
+            return Err(Error::InternalServerError(
+                "SMTP credentials need SMTP_SECURITY starttls or tls".to_string(),
+            ));
+        }
+
+        let connect = TcpStream::connect((self.host.as_str(), self.port));
+        let mut stream: Box<dyn SmtpStream> = Box::new(with_timeout(self.timeout, connect).await??);
+
+        if self.security == SmtpSecurity::Tls {
+            stream = self.connect_tls(stream).await?;
+        }
+
+        let mut session = SmtpSession {
+            stream: BufReader::new(stream),
+            timeout: self.timeout,
+        };
+        session.expect(220).await?;
+        session.command("EHLO localhost", 250).await?;
+
+        if self.security == SmtpSecurity::StartTls {
+            session.command("STARTTLS", 220).await?;
+
+            let stream = self.connect_tls(session.into_stream()).await?;
+            session = SmtpSession {
+                stream: BufReader::new(stream),
+                timeout: self.timeout,
+            };
+            session.command("EHLO localhost", 250).await?;
+        }
+
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            let credentials = STANDARD.encode(format!("\0{}\0{}", username, password));
+            session
+                .command(&format!("AUTH PLAIN {}", credentials), 235)
+                .await?;
+        }
+
+        Ok(session)
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), Error> {
+        let mut session = self.open_session().await?;
+
+        session
+            .command(&format!("MAIL FROM:<{}>", self.from), 250)
+            .await?;
+        session
+            .command(&format!("RCPT TO:<{}>", mail.to), 250)
+            .await?;
+        session.command("DATA", 354).await?;
+
+        // Lines starting with a dot are escaped, a lone dot ends the data
+        let message = mail.to_message(&self.from).replace("\r\n.", "\r\n..");
+        session.command(&format!("{}.", message), 250).await?;
+        session.command("QUIT", 221).await?;
+
+        log::info!("mail sent to {}: {}", mail.to, mail.subject);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MailService {
+    pub mailer: Arc<dyn Mailer>,
+}
+
+impl MailService {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+
+    // MAILER picks the implementation: "smtp", or "file" (the default)
+    pub fn from_config() -> Result<Self, Error> {
+        let from = config::MAIL_FROM.to_string();
+
+        let mailer: Arc<dyn Mailer> = match config::MAILER.as_str() {
+            "smtp" => {
+                let security = SmtpSecurity::parse(&config::SMTP_SECURITY).ok_or_else(|| {
+                    Error::InternalServerError(format!(
+                        "Unknown SMTP_SECURITY: {}",
+                        *config::SMTP_SECURITY
+                    ))
+                })?;
+                if security == SmtpSecurity::None && config::SMTP_USERNAME.is_some() {
+                    return Err(Error::InternalServerError(
+                        "SMTP_USERNAME needs SMTP_SECURITY starttls or tls".to_string(),
+                    ));
+                }
+
+                Arc::new(SmtpMailer {
+                    from,
+                    host: config::SMTP_HOST.to_string(),
+                    port: *config::SMTP_PORT,
+                    security,
+                    username: config::SMTP_USERNAME.clone(),
+                    password: config::SMTP_PASSWORD.clone(),
+                    timeout: Duration::from_secs(*config::SMTP_TIMEOUT_SECS),
+                })
+            }
+            "file" => Arc::new(FileMailer {
+                from,
+                dir: config::MAIL_FILE_DIR.clone().map(PathBuf::from),
+            }),
+            mailer => {
+                return Err(Error::InternalServerError(format!(
+                    "Unknown MAILER: {}",
+                    mailer
+                )));
+            }
+        };
+
+        Ok(Self::new(mailer))
+    }
+
+    pub async fn send_email_verification(
+        &self,
+        email: &str,
+        name: &str,
+        token: &str,
+    ) -> Result<(), Error> {
+        let link = format!("{}?token={}", *config::EMAIL_VERIFICATION_URL, token);
+
+        self.mailer
+            .send(&Mail {
+                to: email.to_string(),
+                subject: "Verify your email address".to_string(),
+                body: format!(
+                    "Hi {},\n\nPlease confirm your email address by opening the link below:\n\n{}\n\n\
+                     The link expires in {} hours. If you didn't sign up, you can ignore this email.",
+                    name,
+                    link,
+                    *config::EMAIL_VERIFICATION_TOKEN_EXPIRATION_HOURS
+                ),
+            })
+            .await
+    }
+
+    pub async fn send_password_reset(
+        &self,
+        email: &str,
+        name: &str,
+        token: &str,
+    ) -> Result<(), Error> {
+        let link = format!("{}?token={}", *config::PASSWORD_RESET_URL, token);
+
+        self.mailer
+            .send(&Mail {
+                to: email.to_string(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Hi {},\n\nYou can choose a new password by opening the link below:\n\n{}\n\n\
+                     The link expires in {} minutes and can only be used once. If you didn't ask \
+                     for a password reset, you can ignore this email.",
+                    name,
+                    link,
+                    *config::PASSWORD_RESET_TOKEN_EXPIRATION_MINUTES
+                ),
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_headers_cannot_be_injected() {
+        let mail = Mail {
+            to: "user@example.com\r\nBcc: victim@example.com".to_string(),
+            subject: "Xác minh email".to_string(),
+            body: "Line 1\n.Line 2".to_string(),
+        };
+
+        let message = mail.to_message("no-reply@example.com");
+
+        assert!(message.contains("To: user@example.com  Bcc: victim@example.com\r\n"));
+        assert!(message.contains(&format!(
+            "Subject: =?UTF-8?B?{}?=\r\n",
+            STANDARD.encode("Xác minh email")
+        )));
+        assert!(message.contains("Message-ID: <"));
+        assert!(message.ends_with("\r\n\r\nLine 1\r\n.Line 2\r\n"));
+    }
+
+    fn smtp_mailer(port: u16, security: SmtpSecurity, username: Option<&str>) -> SmtpMailer {
+        SmtpMailer {
+            from: "no-reply@example.com".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            security,
+            username: username.map(str::to_string),
+            password: username.map(|_| "secret".to_string()),
+            timeout: Duration::from_millis(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_smtp_credentials_are_not_sent_without_tls() {
+        // Refused before connecting
+        let result = smtp_mailer(25, SmtpSecurity::None, Some("user"))
+            .open_session()
+            .await;
+
+        assert!(matches!(result, Err(Error::InternalServerError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_silent_smtp_server_times_out() {
+        // Accepts the connection but never sends its greeting
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let result = smtp_mailer(port, SmtpSecurity::None, None)
+            .open_session()
+            .await;
+
+        assert!(
+            matches!(result, Err(Error::InternalServerError(message)) if message.contains("timed out"))
+        );
+        drop(listener);
+    }
+}
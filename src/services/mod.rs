@@ -1,4 +1,12 @@
+pub mod audit_service;
 pub mod auth_service;
+pub mod event_service;
 pub mod http_request_service;
 pub mod job_service;
+pub mod list_service;
+pub mod mail_service;
+pub mod notification_service;
+pub mod rate_limit_service;
+pub mod tag_service;
+pub mod todo_item_service;
 pub mod todo_service;
\ No newline at end of file
@@ -0,0 +1,62 @@
+use crate::{config, daos::redis_dao::RedisDao, models::errors::Error};
+use chrono::{DateTime, Utc};
+use entity::t_todos;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Messages consumed by the notification workers from the Redis queue
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    TodoReminder {
+        #[serde(rename = "userId")]
+        user_id: Uuid,
+        #[serde(rename = "todoId")]
+        todo_id: Uuid,
+        title: String,
+        #[serde(rename = "dueAt")]
+        due_at: Option<DateTime<Utc>>,
+        #[serde(rename = "remindAt")]
+        remind_at: Option<DateTime<Utc>>,
+    },
+    AccountLocked {
+        #[serde(rename = "userId")]
+        user_id: Uuid,
+        email: String,
+        #[serde(rename = "lockedUntil")]
+        locked_until: DateTime<Utc>,
+    },
+}
+
+impl From<&t_todos::Model> for Notification {
+    fn from(todo: &t_todos::Model) -> Self {
+        Notification::TodoReminder {
+            user_id: todo.user_id,
+            todo_id: todo.id,
+            title: todo.title.clone(),
+            due_at: todo.due_at,
+            remind_at: todo.remind_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationService {
+    pub redis_dao: RedisDao,
+}
+
+impl NotificationService {
+    pub fn new(redis_dao: RedisDao) -> Self {
+        Self { redis_dao }
+    }
+
+    pub async fn enqueue(&self, notification: &Notification) -> Result<(), Error> {
+        let payload = serde_json::to_string(notification)?;
+
+        let mut redis_dao = self.redis_dao.clone();
+        redis_dao
+            .rpush(config::NOTIFICATION_QUEUE_KEY.as_str(), &payload)
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))
+    }
+}
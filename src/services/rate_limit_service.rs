@@ -0,0 +1,111 @@
+use crate::{daos::redis_dao::RedisDao, models::errors::Error};
+use once_cell::sync::Lazy;
+use redis::Script;
+use uuid::Uuid;
+
+const RATE_LIMIT_PREFIX: &str = "ratelimit:";
+
+// Sliding window log: one sorted set entry per request within the window, scored by its time
+// in ms. Uses the Redis clock so every instance agrees on the window.
+// Returns { allowed (0/1), remaining, ms until the oldest request leaves the window }.
+static SLIDING_WINDOW: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        local key = KEYS[1]
+        local limit = tonumber(ARGV[1])
+        local window = tonumber(ARGV[2])
+        local time = redis.call('TIME')
+        local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+        redis.call('ZREMRANGEBYSCORE', key, 0, now - window)
+        local count = redis.call('ZCARD', key)
+        local allowed = 0
+        if count < limit then
+            redis.call('ZADD', key, now, now .. ':' .. ARGV[3])
+            count = count + 1
+            allowed = 1
+        end
+        redis.call('PEXPIRE', key, window)
+
+        local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+        local reset = window
+        if oldest[2] then
+            reset = tonumber(oldest[2]) + window - now
+        end
+        return { allowed, math.max(0, limit - count), reset }
+        ",
+    )
+});
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitStatus {
+    pub allowed: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    // Seconds until a request is allowed again (when limited) or the window is fully reset
+    pub reset_secs: u64,
+}
+
+impl RateLimitStatus {
+    fn from_script(limit: u64, (allowed, remaining, reset_ms): (u8, u64, u64)) -> Self {
+        Self {
+            allowed: allowed == 1,
+            limit,
+            remaining,
+            reset_secs: reset_ms.div_ceil(1000),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService {
+    pub redis_dao: RedisDao,
+}
+
+impl RateLimitService {
+    pub fn new(redis_dao: RedisDao) -> Self {
+        Self { redis_dao }
+    }
+
+    // Counts a request against `key`, a rejected request doesn't use up the limit
+    pub async fn hit(
+        &self,
+        key: &str,
+        max_requests: u64,
+        window_secs: u64,
+    ) -> Result<RateLimitStatus, Error> {
+        let key = format!("{}{}", RATE_LIMIT_PREFIX, key);
+        let (limit, window_ms) = (max_requests.to_string(), (window_secs * 1000).to_string());
+        let request_id = Uuid::new_v4().to_string();
+
+        let mut redis_dao = self.redis_dao.clone();
+        let result = redis_dao
+            .eval_script(&SLIDING_WINDOW, &[&key], &[&limit, &window_ms, &request_id])
+            .await
+            .map_err(|e| Error::InternalServerError(e.to_string()))?;
+
+        Ok(RateLimitStatus::from_script(max_requests, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_is_rounded_up_to_seconds() {
+        assert_eq!(
+            RateLimitStatus::from_script(5, (0, 0, 1200)),
+            RateLimitStatus {
+                allowed: false,
+                limit: 5,
+                remaining: 0,
+                reset_secs: 2,
+            }
+        );
+        assert_eq!(
+            RateLimitStatus::from_script(5, (1, 4, 60000)).reset_secs,
+            60
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    models::{
+        errors::Error,
+        request::{CreateTagRequest, SetTodoTagsRequest, UpdateTagRequest},
+        response::CommonResponse,
+    },
+    repositories::{tag_repository::TagRepository, todo_repository::TodoRepository},
+};
+use chrono::Utc;
+use entity::t_tags;
+use sea_orm::{ActiveValue::Set, IntoActiveModel, SqlErr};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct TagService {
+    pub tag_repository: TagRepository,
+    pub todo_repository: TodoRepository,
+}
+
+impl TagService {
+    pub fn new(tag_repository: TagRepository, todo_repository: TodoRepository) -> Self {
+        Self {
+            tag_repository,
+            todo_repository,
+        }
+    }
+
+    fn parse_id(id: &str, kind: &str) -> Result<Uuid, Error> {
+        Uuid::parse_str(id).map_err(|_| Error::BadRequest(format!("Invalid {} id", kind)))
+    }
+
+    // The unique index on (user_id, LOWER(name)) reports duplicated names
+    fn map_duplicate_name(error: Error, name: &str) -> Error {
+        match &error {
+            Error::Db(db_err)
+                if matches!(db_err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) =>
+            {
+                Error::Conflict(format!("Tag {} already exists", name))
+            }
+            _ => error,
+        }
+    }
+
+    async fn find_tag(&self, id: String, user_id: Uuid) -> Result<t_tags::Model, Error> {
+        let id = Self::parse_id(&id, "tag")?;
+
+        self.tag_repository
+            .find_by_id(id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Tag with id {} not found", id)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all_tags(&self, user_id: Uuid) -> Result<Vec<t_tags::Model>, Error> {
+        self.tag_repository.get_all_tags(user_id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_tag_by_id(&self, id: String, user_id: Uuid) -> Result<t_tags::Model, Error> {
+        self.find_tag(id, user_id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn create_tag(
+        &self,
+        body: CreateTagRequest,
+        user_id: Uuid,
+    ) -> Result<t_tags::Model, Error> {
+        let name = body.name.clone();
+
+        self.tag_repository
+            .create(body.into_active_model(user_id))
+            .await
+            .map_err(|e| Self::map_duplicate_name(e, &name))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn update_tag(
+        &self,
+        id: String,
+        body: UpdateTagRequest,
+        user_id: Uuid,
+    ) -> Result<t_tags::Model, Error> {
+        let tag = self.find_tag(id, user_id).await?;
+        let name = body.name.clone().unwrap_or_else(|| tag.name.clone());
+        let mut tag = tag.into_active_model();
+
+        if let Some(name) = body.name {
+            tag.name = Set(name.trim().to_string());
+        }
+
+        if let Some(color) = body.color {
+            tag.color = Set(Some(color));
+        }
+
+        tag.updated_at = Set(Utc::now());
+        self.tag_repository
+            .update(tag)
+            .await
+            .map_err(|e| Self::map_duplicate_name(e, &name))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_tag(
+        &self,
+        id: String,
+        user_id: Uuid,
+    ) -> Result<CommonResponse<String>, Error> {
+        let id = Self::parse_id(&id, "tag")?;
+
+        if self.tag_repository.delete(id, user_id).await? == 0 {
+            return Err(Error::NotFound(format!("Tag with id {} not found", id)));
+        }
+
+        Ok(CommonResponse {
+            message: format!("Deleted tag id {} successfully", id),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_todo_tags(
+        &self,
+        todo_id: String,
+        user_id: Uuid,
+    ) -> Result<Vec<t_tags::Model>, Error> {
+        let todo_id = Self::parse_id(&todo_id, "todo")?;
+
+        self.todo_repository
+            .find_by_id(todo_id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", todo_id)))?;
+
+        self.tag_repository.get_todo_tags(todo_id, user_id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn set_todo_tags(
+        &self,
+        todo_id: String,
+        body: SetTodoTagsRequest,
+        user_id: Uuid,
+    ) -> Result<Vec<t_tags::Model>, Error> {
+        let todo_id = Self::parse_id(&todo_id, "todo")?;
+
+        self.todo_repository
+            .find_by_id(todo_id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", todo_id)))?;
+
+        let mut tag_ids = body.tag_ids;
+        tag_ids.sort();
+        tag_ids.dedup();
+
+        // Someone else's tags are reported as missing, like their todos
+        let tags = self.tag_repository.find_by_ids(&tag_ids, user_id).await?;
+        if let Some(missing) = tag_ids
+            .iter()
+            .find(|id| !tags.iter().any(|tag| tag.id == **id))
+        {
+            return Err(Error::NotFound(format!(
+                "Tag with id {} not found",
+                missing
+            )));
+        }
+
+        self.tag_repository
+            .set_todo_tags(todo_id, user_id, tag_ids)
+            .await?;
+        Ok(tags)
+    }
+}
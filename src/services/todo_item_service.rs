@@ -0,0 +1,178 @@
+use crate::{
+    models::{
+        errors::Error,
+        request::{CreateTodoItemRequest, ReorderTodoItemsRequest, UpdateTodoItemRequest},
+        response::CommonResponse,
+    },
+    repositories::todo_item_repository::TodoItemRepository,
+    services::todo_service::TodoService,
+};
+use chrono::Utc;
+use entity::t_todo_items;
+use sea_orm::{ActiveValue::Set, IntoActiveModel};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct TodoItemService {
+    pub todo_item_repository: TodoItemRepository,
+    pub todo_service: TodoService,
+}
+
+impl TodoItemService {
+    pub fn new(todo_item_repository: TodoItemRepository, todo_service: TodoService) -> Self {
+        Self {
+            todo_item_repository,
+            todo_service,
+        }
+    }
+
+    fn parse_id(id: &str, kind: &str) -> Result<Uuid, Error> {
+        Uuid::parse_str(id).map_err(|_| Error::BadRequest(format!("Invalid {} id", kind)))
+    }
+
+    // Items are reached through their todo, so the access to the todo covers them
+    async fn find_todo_id(&self, todo_id: String, user_id: Uuid) -> Result<Uuid, Error> {
+        let todo_id = Self::parse_id(&todo_id, "todo")?;
+
+        self.todo_service
+            .todo_repository
+            .find_by_id(todo_id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", todo_id)))?;
+
+        Ok(todo_id)
+    }
+
+    // Changing items needs write access to the todo
+    async fn find_editable_todo_id(&self, todo_id: String, user_id: Uuid) -> Result<Uuid, Error> {
+        let todo_id = Self::parse_id(&todo_id, "todo")?;
+
+        self.todo_service
+            .todo_repository
+            .find_editable_by_id(todo_id, user_id)
+            .await?;
+
+        Ok(todo_id)
+    }
+
+    async fn find_item(
+        &self,
+        todo_id: Uuid,
+        item_id: String,
+    ) -> Result<t_todo_items::Model, Error> {
+        let item_id = Self::parse_id(&item_id, "item")?;
+
+        self.todo_item_repository
+            .find_by_id(item_id, todo_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Item with id {} not found", item_id)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_items(
+        &self,
+        todo_id: String,
+        user_id: Uuid,
+    ) -> Result<Vec<t_todo_items::Model>, Error> {
+        let todo_id = self.find_todo_id(todo_id, user_id).await?;
+        self.todo_item_repository.get_items(todo_id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn create_item(
+        &self,
+        todo_id: String,
+        body: CreateTodoItemRequest,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<t_todo_items::Model, Error> {
+        let todo_id = self.find_editable_todo_id(todo_id, user_id).await?;
+        let completed = body.completed.unwrap_or(false);
+
+        let item = self
+            .todo_item_repository
+            .create(body.into_active_model(), todo_id)
+            .await?;
+
+        if completed {
+            self.todo_service
+                .complete_if_items_done(todo_id, user_id, &ip)
+                .await?;
+        }
+
+        Ok(item)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn update_item(
+        &self,
+        todo_id: String,
+        item_id: String,
+        body: UpdateTodoItemRequest,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<t_todo_items::Model, Error> {
+        let todo_id = self.find_editable_todo_id(todo_id, user_id).await?;
+        let mut item = self.find_item(todo_id, item_id).await?.into_active_model();
+
+        if let Some(title) = body.title {
+            item.title = Set(title.trim().to_string());
+        }
+
+        if let Some(completed) = body.completed {
+            item.completed = Set(completed);
+        }
+
+        item.updated_at = Set(Utc::now());
+        let item = self.todo_item_repository.update(item).await?;
+
+        if item.completed {
+            self.todo_service
+                .complete_if_items_done(todo_id, user_id, &ip)
+                .await?;
+        }
+
+        Ok(item)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_item(
+        &self,
+        todo_id: String,
+        item_id: String,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
+        let todo_id = self.find_editable_todo_id(todo_id, user_id).await?;
+        let item_id = Self::parse_id(&item_id, "item")?;
+
+        if self.todo_item_repository.delete(item_id, todo_id).await? == 0 {
+            return Err(Error::NotFound(format!(
+                "Item with id {} not found",
+                item_id
+            )));
+        }
+
+        // Removing the last open item completes the todo as well
+        self.todo_service
+            .complete_if_items_done(todo_id, user_id, &ip)
+            .await?;
+
+        Ok(CommonResponse {
+            message: format!("Deleted item id {} successfully", item_id),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn reorder_items(
+        &self,
+        todo_id: String,
+        body: ReorderTodoItemsRequest,
+        user_id: Uuid,
+    ) -> Result<Vec<t_todo_items::Model>, Error> {
+        let todo_id = self.find_editable_todo_id(todo_id, user_id).await?;
+        self.todo_item_repository
+            .reorder(todo_id, body.item_ids)
+            .await
+    }
+}
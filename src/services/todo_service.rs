@@ -2,23 +2,44 @@ use crate::{
     config,
     daos::redis_dao::{RedisDao, RedisOperations},
     models::{
-        errors::Error,
-        request::{self, GetAllTodosRequest, UpdateTodoRequest},
-        response::{CommonResponse, GetAllTodosResponse},
+        errors::{Error, ErrorToHttp},
+        request::{
+            self, BatchTodoOperation, BatchTodosRequest, GetAllTodosRequest, GetAuditLogRequest,
+            GetTrashTodosRequest, PatchTodoRequest, TodoFilters, TodoSort, UpdateTodoRequest,
+        },
+        response::{
+            BatchTodoResult, BatchTodosResponse, CommonResponse, GetAllTodosResponse,
+            GetAuditLogResponse, TodoListItem,
+        },
     },
-    repositories::todo_repository::TodoRepository,
-    services::http_request_service::{HttpRequestError, HttpRequestService},
+    repositories::{
+        todo_item_repository::TodoItemRepository,
+        todo_repository::{TodoRepository, TodoWrite},
+    },
+    services::{
+        audit_service::AuditService,
+        event_service::{EventService, TodoEvent, TodoEventMessage, TodoEventType},
+        http_request_service::{HttpRequestError, HttpRequestService},
+    },
+    utils::cursor::TodoCursor,
+};
+use actix_web::http::StatusCode;
+use entity::{
+    sea_orm_active_enums::{AuditAction, AuditEntityType},
+    t_todos,
 };
-use chrono::Utc;
-use entity::t_todos;
-use sea_orm::{ActiveValue::Set, IntoActiveModel};
+use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Clone)]
 pub struct TodoService {
     pub todo_repository: TodoRepository,
+    pub todo_item_repository: TodoItemRepository,
+    pub audit_service: AuditService,
+    pub event_service: EventService,
     pub redis_dao: RedisDao,
     pub http_request_service: HttpRequestService,
 }
@@ -43,26 +64,82 @@ pub struct ExternalTodosResponse {
 impl TodoService {
     pub fn new(
         todo_repository: TodoRepository,
+        todo_item_repository: TodoItemRepository,
+        audit_service: AuditService,
+        event_service: EventService,
         redis_dao: RedisDao,
     ) -> Result<Self, HttpRequestError> {
         Ok(Self {
             todo_repository,
+            todo_item_repository,
+            audit_service,
+            event_service,
             redis_dao,
             http_request_service: HttpRequestService::new()?,
         })
     }
 
+    // Cursors encode a position in the default `createdAt:desc` ordering only
+    fn supports_cursor(filters: &TodoFilters) -> bool {
+        filters.sort == TodoSort::default() && filters.text_query.is_none()
+    }
+
+    fn next_cursor(
+        todos: &[t_todos::Model],
+        has_next: bool,
+        filters: &TodoFilters,
+    ) -> Option<String> {
+        if !has_next || !Self::supports_cursor(filters) {
+            return None;
+        }
+
+        todos
+            .last()
+            .map(|todo| TodoCursor::new(todo.created_at, todo.id).encode())
+    }
+
+    // One query for the whole page instead of one per todo
+    async fn with_item_counts<T: Into<TodoListItem>>(
+        &self,
+        todos: Vec<T>,
+    ) -> Result<Vec<TodoListItem>, Error> {
+        let mut items: Vec<TodoListItem> = todos.into_iter().map(Into::into).collect();
+        let ids: Vec<Uuid> = items.iter().map(|item| item.todo.id).collect();
+        let counts = self.todo_item_repository.count_by_todos(&ids).await?;
+
+        for item in items.iter_mut() {
+            if let Some(counts) = counts.get(&item.todo.id) {
+                item.item_count = counts.total;
+                item.completed_item_count = counts.completed;
+            }
+        }
+
+        Ok(items)
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn create_todo(
         &self,
         body: request::CreateTodoRequest,
         user_id: Uuid,
+        ip: String,
     ) -> Result<t_todos::Model, Error> {
         let start_time = Instant::now();
 
+        if let Some(list_id) = body.list_id {
+            self.todo_repository
+                .check_list_access(list_id, user_id)
+                .await?;
+        }
+
         let todo_model = body.into_active_model(user_id);
         let todo = self.todo_repository.create(todo_model).await?;
 
+        self.audit_service
+            .record_todo(user_id, AuditAction::Create, None, Some(&todo), &ip)
+            .await;
+        self.publish_event(TodoEventType::Created, &todo).await;
+
         log::info!("create_todo took {}ms", start_time.elapsed().as_millis());
         Ok(todo)
     }
@@ -71,88 +148,543 @@ impl TodoService {
     pub async fn get_all_todos(
         &self,
         params: GetAllTodosRequest,
+        user_id: Uuid,
     ) -> Result<GetAllTodosResponse, Error> {
         log::info!("Getting all todos with params: {:?}", &params);
 
-        let limit = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
-        let offset = (params.page.unwrap_or(*config::DEFAULT_PAGE) - 1) * limit;
+        let page_size = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
+        let filters = params.filters();
+
+        if let Some(cursor) = params.cursor {
+            if params.page.is_some() {
+                return Err(Error::BadRequest(
+                    "cursor and page cannot be used together".to_string(),
+                ));
+            }
+
+            if !Self::supports_cursor(&filters) {
+                return Err(Error::BadRequest(
+                    "cursor cannot be combined with sort or q".to_string(),
+                ));
+            }
 
-        let todos = self
+            let cursor = TodoCursor::decode(&cursor)
+                .ok_or_else(|| Error::BadRequest("Invalid cursor".to_string()))?;
+
+            let (todos, has_next) = self
+                .todo_repository
+                .get_todos_after_cursor(user_id, page_size, Some(cursor), &filters)
+                .await?;
+
+            return Ok(GetAllTodosResponse {
+                page: None,
+                page_size,
+                total: None,
+                total_pages: None,
+                has_next,
+                next_cursor: Self::next_cursor(&todos, has_next, &filters),
+                data: self.with_item_counts(todos).await?,
+            });
+        }
+
+        let page = params.page.unwrap_or(*config::DEFAULT_PAGE);
+
+        if let Some(text_query) = &filters.text_query {
+            let (rows, total) = self
+                .todo_repository
+                .search_todos(
+                    user_id,
+                    page_size,
+                    page.saturating_sub(1),
+                    text_query,
+                    &filters,
+                )
+                .await?;
+
+            let total_pages = total.div_ceil(page_size);
+
+            return Ok(GetAllTodosResponse {
+                page: Some(page),
+                page_size,
+                total: Some(total),
+                total_pages: Some(total_pages),
+                has_next: page < total_pages,
+                next_cursor: None,
+                data: self.with_item_counts(rows).await?,
+            });
+        }
+
+        // page is 1-based in the API, SeaORM pages are 0-based
+        let (todos, total) = self
             .todo_repository
-            .get_all_todos(limit, offset, params.search)
+            .get_all_todos(user_id, page_size, page.saturating_sub(1), &filters)
             .await?;
 
-        let total = todos.len();
-        let total_pages = (total as f64 / limit as f64).ceil() as usize;
+        let total_pages = total.div_ceil(page_size);
+
+        let has_next = page < total_pages;
 
+        // nextCursor lets clients switch from page mode to cursor mode
         Ok(GetAllTodosResponse {
-            total,
-            total_pages,
-            data: todos,
+            page: Some(page),
+            page_size,
+            total: Some(total),
+            total_pages: Some(total_pages),
+            has_next,
+            next_cursor: Self::next_cursor(&todos, has_next, &filters),
+            data: self.with_item_counts(todos).await?,
         })
     }
 
+    // Admin view of another user's todos
+    #[tracing::instrument(skip(self))]
+    pub async fn get_user_todos(
+        &self,
+        user_id: String,
+        params: GetAllTodosRequest,
+    ) -> Result<GetAllTodosResponse, Error> {
+        let user_id = Uuid::parse_str(&user_id)
+            .map_err(|_| Error::BadRequest("Invalid user id".to_string()))?;
+
+        self.get_all_todos(params, user_id).await
+    }
+
     #[tracing::instrument(skip(self))]
-    pub async fn get_todo_by_id(&self, id: String) -> Result<t_todos::Model, Error> {
+    pub async fn get_todo_by_id(&self, id: String, user_id: Uuid) -> Result<t_todos::Model, Error> {
         let id =
             Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid todo id".to_string()))?;
 
+        // Foreign todos are reported as missing so their existence isn't leaked
         let todo = self
             .todo_repository
-            .find_by_id(id)
+            .find_by_id(id, user_id)
             .await?
-            .ok_or_else(|| Error::BadRequest(format!("Todo with id {} not found", id)))?;
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", id)))?;
 
         Ok(todo)
     }
 
     #[tracing::instrument(skip(self))]
-    pub async fn delete_todo(&self, id: String) -> Result<CommonResponse<String>, Error> {
+    pub async fn delete_todo(
+        &self,
+        id: String,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<CommonResponse<String>, Error> {
         let id =
             Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid todo id".to_string()))?;
 
-        self.todo_repository.delete(id).await?;
+        // Viewers of the todo's list are refused before anything is written
+        let todo = self
+            .todo_repository
+            .find_editable_by_id(id, user_id)
+            .await?;
+        let deleted = self
+            .todo_repository
+            .delete(id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found", id)))?;
+
+        self.audit_service
+            .record_todo(
+                user_id,
+                AuditAction::Delete,
+                Some(&todo),
+                Some(&deleted),
+                &ip,
+            )
+            .await;
+        self.publish_event(TodoEventType::Deleted, &deleted).await;
 
         Ok(CommonResponse {
             message: format!("Deleted todo id {} successfully", id),
         })
     }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn restore_todo(
+        &self,
+        id: String,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<t_todos::Model, Error> {
+        let id =
+            Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid todo id".to_string()))?;
+
+        let restored = self
+            .todo_repository
+            .restore(id, user_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Todo with id {} not found in trash", id)))?;
+
+        // Only deletedAt changes, which the action already says
+        self.audit_service
+            .record(
+                user_id,
+                AuditEntityType::Todo,
+                id,
+                AuditAction::Restore,
+                None,
+                &ip,
+            )
+            .await;
+        // Back in the todo list of the clients
+        self.publish_event(TodoEventType::Created, &restored).await;
+
+        Ok(restored)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_todo_history(
+        &self,
+        id: String,
+        user_id: Uuid,
+        params: GetAuditLogRequest,
+    ) -> Result<GetAuditLogResponse, Error> {
+        let todo = self.get_todo_by_id(id, user_id).await?;
+
+        self.audit_service
+            .get_entity_history(AuditEntityType::Todo, todo.id, params)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_trash(
+        &self,
+        params: GetTrashTodosRequest,
+        user_id: Uuid,
+    ) -> Result<GetAllTodosResponse, Error> {
+        let page_size = params.page_size.unwrap_or(*config::DEFAULT_PAGE_SIZE);
+        let page = params.page.unwrap_or(*config::DEFAULT_PAGE);
+
+        let (todos, total) = self
+            .todo_repository
+            .get_trash(user_id, page_size, page.saturating_sub(1))
+            .await?;
+
+        let total_pages = total.div_ceil(page_size);
+
+        Ok(GetAllTodosResponse {
+            page: Some(page),
+            page_size,
+            total: Some(total),
+            total_pages: Some(total_pages),
+            has_next: page < total_pages,
+            next_cursor: None,
+            data: self.with_item_counts(todos).await?,
+        })
+    }
+
+    // `if_match` holds the versions accepted by the client, None skips the check
     #[tracing::instrument(skip(self))]
     pub async fn update_todo(
         &self,
         id: String,
         body: UpdateTodoRequest,
+        user_id: Uuid,
+        if_match: Option<Vec<i32>>,
+        ip: String,
+    ) -> Result<t_todos::Model, Error> {
+        self.save_todo_changes(id, user_id, if_match, body.into_active_model(), &ip)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn patch_todo(
+        &self,
+        id: String,
+        body: PatchTodoRequest,
+        user_id: Uuid,
+        if_match: Option<Vec<i32>>,
+        ip: String,
+    ) -> Result<t_todos::Model, Error> {
+        self.save_todo_changes(id, user_id, if_match, body.into_active_model(), &ip)
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn batch_todos(
+        &self,
+        body: BatchTodosRequest,
+        user_id: Uuid,
+        ip: String,
+    ) -> Result<BatchTodosResponse, Error> {
+        let start_time = Instant::now();
+        let total = body.operations.len();
+
+        // Invalid operations reject the whole batch before anything is written
+        let mut writes = Vec::with_capacity(total);
+        let mut actions = Vec::with_capacity(total);
+        for (index, operation) in body.operations.into_iter().enumerate() {
+            let invalid = |e: validator::ValidationErrors| {
+                Error::BadRequest(format!("Invalid operation at index {}: {}", index, e))
+            };
+
+            let write = match operation {
+                BatchTodoOperation::Create { data } => {
+                    data.validate().map_err(invalid)?;
+                    TodoWrite::Create(data.into_active_model(user_id))
+                }
+                BatchTodoOperation::Update { id, version, data } => {
+                    data.validate().map_err(invalid)?;
+                    TodoWrite::Update {
+                        id,
+                        version,
+                        changes: data.into_active_model(),
+                    }
+                }
+                BatchTodoOperation::Delete { id } => TodoWrite::Delete(id),
+            };
+            actions.push(match write {
+                TodoWrite::Create(_) => AuditAction::Create,
+                TodoWrite::Update { .. } => AuditAction::Update,
+                TodoWrite::Delete(_) => AuditAction::Delete,
+            });
+            writes.push(write);
+        }
+
+        let results = self
+            .todo_repository
+            .apply_batch(user_id, writes, body.atomic)
+            .await?;
+
+        let committed = !body.atomic || results.iter().all(Result::is_ok);
+
+        // Nothing of a rolled back batch is recorded
+        if committed {
+            for (action, (before, after)) in Self::successful_writes(&actions, &results) {
+                // Same follow-up as a single update, a completed recurring todo gets its next one
+                if let (AuditAction::Update, Some(before)) = (action, before) {
+                    self.after_update(user_id, before, after, &ip).await;
+                    continue;
+                }
+
+                self.audit_service
+                    .record_todo(user_id, *action, before.as_ref(), Some(after), &ip)
+                    .await;
+
+                let event_type = match action {
+                    AuditAction::Create => TodoEventType::Created,
+                    AuditAction::Delete => TodoEventType::Deleted,
+                    _ => TodoEventType::Updated,
+                };
+                self.publish_event(event_type, after).await;
+            }
+        }
+
+        let mut results: Vec<BatchTodoResult> = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| match result {
+                Ok((_, todo)) => BatchTodoResult {
+                    index,
+                    status_code: StatusCode::OK.as_u16(),
+                    data: Some(todo),
+                    message: None,
+                },
+                Err(e) => Self::batch_error(index, e),
+            })
+            .collect();
+
+        // A rolled back batch reports what happened to every operation, not only the failed one
+        if !committed {
+            for result in results.iter_mut().filter(|result| result.data.is_some()) {
+                result.status_code = StatusCode::FAILED_DEPENDENCY.as_u16();
+                result.data = None;
+                result.message = Some("Rolled back".to_string());
+            }
+
+            let executed = results.len();
+            results.extend((executed..total).map(|index| BatchTodoResult {
+                index,
+                status_code: StatusCode::FAILED_DEPENDENCY.as_u16(),
+                data: None,
+                message: Some("Not executed".to_string()),
+            }));
+        }
+
+        log::info!(
+            "batch_todos ran {} operation(s) in {}ms",
+            total,
+            start_time.elapsed().as_millis()
+        );
+
+        Ok(BatchTodosResponse { committed, results })
+    }
+
+    // Pairs every successful write with its action, failed writes are skipped without shifting
+    // the ones after them
+    fn successful_writes<'a, T>(
+        actions: &'a [AuditAction],
+        results: &'a [Result<T, Error>],
+    ) -> impl Iterator<Item = (&'a AuditAction, &'a T)> {
+        actions
+            .iter()
+            .zip(results)
+            .filter_map(|(action, result)| result.as_ref().ok().map(|write| (action, write)))
+    }
+
+    fn batch_error(index: usize, error: Error) -> BatchTodoResult {
+        let status = error.to_http_response().status();
+
+        // Don't leak database details, they are logged by to_http_response
+        let message = if status.is_server_error() {
+            "Internal server error".to_string()
+        } else {
+            error.to_string()
+        };
+
+        BatchTodoResult {
+            index,
+            status_code: status.as_u16(),
+            data: None,
+            message: Some(message),
+        }
+    }
+
+    // Only the columns set on `changes` are written, version is bumped by the repository
+    async fn save_todo_changes(
+        &self,
+        id: String,
+        user_id: Uuid,
+        if_match: Option<Vec<i32>>,
+        changes: t_todos::ActiveModel,
+        ip: &str,
     ) -> Result<t_todos::Model, Error> {
         let id =
             Uuid::parse_str(&id).map_err(|_| Error::BadRequest("Invalid todo id".to_string()))?;
 
-        let mut todo_active_model = self
+        let todo = self
             .todo_repository
-            .find_by_id(id)
+            .find_editable_by_id(id, user_id)
+            .await?;
+
+        if let Some(versions) = if_match
+            && !versions.contains(&todo.version)
+        {
+            return Err(Error::PreconditionFailed(format!(
+                "Todo with id {} has been modified",
+                id
+            )));
+        }
+
+        // Lost the race against a concurrent update between the read and the write
+        let updated = self
+            .todo_repository
+            .update(&todo, changes)
             .await?
-            .ok_or_else(|| Error::BadRequest(format!("Todo with id {} not found", id)))?
-            .into_active_model();
+            .ok_or_else(|| {
+                Error::PreconditionFailed(format!("Todo with id {} has been modified", id))
+            })?;
+
+        self.after_update(user_id, &todo, &updated, ip).await;
+        Ok(updated)
+    }
+
+    // Records the update, and schedules the next todo when a recurring one is completed. The
+    // recurrence job retries on failure.
+    async fn after_update(
+        &self,
+        user_id: Uuid,
+        before: &t_todos::Model,
+        updated: &t_todos::Model,
+        ip: &str,
+    ) {
+        self.audit_service
+            .record_todo(
+                user_id,
+                AuditAction::Update,
+                Some(before),
+                Some(updated),
+                ip,
+            )
+            .await;
+        self.publish_event(TodoEventType::Updated, updated).await;
 
-        if let Some(title) = body.title {
-            todo_active_model.title = Set(title);
+        if before.completed || !updated.completed || updated.recurrence.is_none() {
+            return;
+        }
+
+        let id = updated.id;
+        match self.todo_repository.create_next_occurrence(updated).await {
+            Ok(Some(next)) => {
+                log::info!("created next occurrence {} of todo {}", next.id, id);
+                self.publish_event(TodoEventType::Created, &next).await;
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("failed to create next occurrence of todo {}: {:?}", id, e),
         }
+    }
+
+    // Pushes the change to the event streams of everyone who can see the todo. Like auditing,
+    // a failure is logged and doesn't fail the write, clients catch up on their next fetch.
+    async fn publish_event(&self, event_type: TodoEventType, todo: &t_todos::Model) {
+        let user_ids = match self.todo_repository.find_audience(todo).await {
+            Ok(user_ids) => user_ids,
+            Err(e) => {
+                log::error!("failed to find the audience of todo {}: {:?}", todo.id, e);
+                return;
+            }
+        };
+
+        let message = TodoEventMessage {
+            user_ids,
+            event: TodoEvent {
+                event_type,
+                todo: todo.clone(),
+            },
+        };
 
-        if let Some(description) = body.description {
-            todo_active_model.description = Set(description);
+        if let Err(e) = self.event_service.publish(&message).await {
+            log::error!(
+                "failed to publish {:?} of todo {}: {:?}",
+                event_type,
+                todo.id,
+                e
+            );
         }
+    }
+
+    // Completes the todo once all of its items are done. Unchecking an item later leaves the
+    // todo completed, and losing a race against another update only skips the completion.
+    pub async fn complete_if_items_done(
+        &self,
+        todo_id: Uuid,
+        user_id: Uuid,
+        ip: &str,
+    ) -> Result<(), Error> {
+        let Some(todo) = self.todo_repository.find_by_id(todo_id, user_id).await? else {
+            return Ok(());
+        };
 
-        if let Some(completed) = body.completed {
-            todo_active_model.completed = Set(completed);
+        if todo.completed
+            || !self
+                .todo_item_repository
+                .count_by_todo(todo_id)
+                .await?
+                .all_completed()
+        {
+            return Ok(());
         }
 
-        todo_active_model.updated_at = Set(Utc::now().into());
-        let updated_todo = self.todo_repository.update(todo_active_model).await?;
+        let changes = t_todos::ActiveModel {
+            completed: Set(true),
+            ..Default::default()
+        };
 
-        Ok(updated_todo)
+        match self.todo_repository.update(&todo, changes).await? {
+            Some(updated) => {
+                log::info!("completed todo {} after its last item", todo_id);
+                self.after_update(user_id, &todo, &updated, ip).await;
+            }
+            None => log::info!("todo {} changed while completing its items", todo_id),
+        }
+
+        Ok(())
     }
 
     // Test call external API with cache
+    #[allow(clippy::manual_unwrap_or_default)]
     pub async fn get_external_data(&self) -> Result<ExternalTodosResponse, Error> {
         let key = "EXTERNAL_TODOS";
 
@@ -183,3 +715,32 @@ impl TodoService {
         Ok(resp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_successful_writes_skip_failed_operations() {
+        let actions = [
+            AuditAction::Update,
+            AuditAction::Create,
+            AuditAction::Delete,
+        ];
+        let results = [
+            Err(Error::NotFound("Todo not found".to_string())),
+            Ok("created"),
+            Ok("deleted"),
+        ];
+
+        let writes: Vec<_> = TodoService::successful_writes(&actions, &results).collect();
+
+        assert_eq!(
+            writes,
+            vec![
+                (&AuditAction::Create, &"created"),
+                (&AuditAction::Delete, &"deleted")
+            ]
+        );
+    }
+}
@@ -1,28 +1,246 @@
-use actix_web::dev::ServiceRequest;
-
-pub fn get_client_ip(req: &ServiceRequest) -> String {
-    // 1. X-Forwarded-For
-    if let Some(forwarded_for) = req.headers().get("x-forwarded-for") {
-        if let Ok(forwarded_for) = forwarded_for.to_str() {
-            // get first ip
-            if let Some(ip) = forwarded_for.split(',').next() {
-                return ip.trim().to_string();
-            }
+use crate::config;
+use actix_web::{FromRequest, HttpRequest, dev, http::header::HeaderMap};
+use ipnet::IpNet;
+use std::{
+    future::{Ready, ready},
+    net::{IpAddr, SocketAddr},
+};
+
+// Header the trusted proxies write the client address to. Only that header is read, any other
+// forwarding header may have been sent by the client itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyHeader {
+    // RFC 7239 `Forwarded: for=...`
+    Forwarded,
+    XForwardedFor,
+    XRealIp,
+}
+
+impl ProxyHeader {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "forwarded" => Some(Self::Forwarded),
+            "x-forwarded-for" => Some(Self::XForwardedFor),
+            "x-real-ip" => Some(Self::XRealIp),
+            _ => None,
+        }
+    }
+}
+
+pub fn get_client_ip(req: &HttpRequest) -> String {
+    resolve_client_ip(req, &config::TRUSTED_PROXIES, *config::PROXY_HEADER)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+// Forwarding headers can be written by anyone, so they are only believed when they were added
+// by a trusted proxy. The hops are walked from the right (closest to us) and the first one that
+// isn't a trusted proxy is the client.
+fn resolve_client_ip(
+    req: &HttpRequest,
+    trusted_proxies: &[IpNet],
+    header: ProxyHeader,
+) -> Option<IpAddr> {
+    let peer_ip = req.peer_addr()?.ip();
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+
+    if !is_trusted(&peer_ip) {
+        return Some(peer_ip);
+    }
+
+    let hops = forwarded_hops(req.headers(), header);
+    let mut client_ip = peer_ip;
+    for hop in hops.iter().rev() {
+        // An obfuscated or garbled hop can't be followed any further
+        let Some(ip) = parse_node(hop) else {
+            break;
+        };
+
+        client_ip = ip;
+        if !is_trusted(&ip) {
+            break;
         }
     }
 
-    // 2. X-Real-IP
-    if let Some(real_ip) = req.headers().get("x-real-ip") {
-        if let Ok(real_ip) = real_ip.to_str() {
-            return real_ip.trim().to_string();
+    Some(client_ip)
+}
+
+// Hops listed in `header`. Multiple header lines are one list in the order they were received.
+fn forwarded_hops(headers: &HeaderMap, header: ProxyHeader) -> Vec<String> {
+    let name = match header {
+        ProxyHeader::Forwarded => "forwarded",
+        ProxyHeader::XForwardedFor => "x-forwarded-for",
+        ProxyHeader::XRealIp => "x-real-ip",
+    };
+    let elements = headers
+        .get_all(name)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+
+    if header != ProxyHeader::Forwarded {
+        return elements
+            .map(|hop| hop.trim().to_string())
+            .filter(|hop| !hop.is_empty())
+            .collect();
+    }
+
+    elements
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .collect()
+}
+
+// Accepts `192.0.2.1`, `192.0.2.1:8080`, `2001:db8::1` and `[2001:db8::1]:8080`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim();
+    node.parse::<IpAddr>()
+        .or_else(|_| node.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .or_else(|_| {
+            node.strip_prefix('[')
+                .and_then(|node| node.strip_suffix(']'))
+                .unwrap_or_default()
+                .parse::<IpAddr>()
+        })
+        .ok()
+}
+
+// Extractor for the client IP, recorded in the audit log
+#[derive(Debug, Clone)]
+pub struct ClientIp(pub String);
+
+impl FromRequest for ClientIp {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, actix_web::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        ready(Ok(ClientIp(get_client_ip(req))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn trusted() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()]
+    }
+
+    fn client_ip(peer: &str, headers: &[(&str, &str)]) -> Option<String> {
+        client_ip_from(ProxyHeader::XForwardedFor, peer, headers)
+    }
+
+    fn client_ip_from(header: ProxyHeader, peer: &str, headers: &[(&str, &str)]) -> Option<String> {
+        let mut req = TestRequest::default().peer_addr(peer.parse().unwrap());
+        for header in headers {
+            req = req.append_header(*header);
         }
+
+        resolve_client_ip(&req.to_http_request(), &trusted(), header).map(|ip| ip.to_string())
+    }
+
+    #[test]
+    fn test_headers_of_untrusted_peers_are_ignored() {
+        let ip = client_ip(
+            "203.0.113.7:5000",
+            &[
+                ("x-forwarded-for", "1.2.3.4"),
+                ("forwarded", "for=1.2.3.4"),
+                ("x-real-ip", "1.2.3.4"),
+            ],
+        );
+
+        assert_eq!(ip.as_deref(), Some("203.0.113.7"));
     }
 
-    // 3. peer_addr (no proxy)
-    if let Some(peer_ip) = req.connection_info().peer_addr() {
-        return peer_ip.to_string();
+    #[test]
+    fn test_spoofed_forwarded_for_entries_are_skipped() {
+        // The client sent "X-Forwarded-For: 1.2.3.4", our proxy appended the real address
+        let ip = client_ip(
+            "10.0.0.1:5000",
+            &[("x-forwarded-for", "1.2.3.4, 198.51.100.9")],
+        );
+        assert_eq!(ip.as_deref(), Some("198.51.100.9"));
+
+        // Split over several header lines and behind two proxies
+        let ip = client_ip(
+            "10.0.0.1:5000",
+            &[
+                ("x-forwarded-for", "1.2.3.4"),
+                ("x-forwarded-for", "198.51.100.9, 10.0.0.2"),
+            ],
+        );
+        assert_eq!(ip.as_deref(), Some("198.51.100.9"));
+    }
+
+    #[test]
+    fn test_other_forwarding_headers_are_ignored() {
+        // The proxy only appends to X-Forwarded-For, the rest was sent by the client
+        let spoofed = [
+            ("forwarded", "for=1.2.3.4"),
+            ("x-real-ip", "1.2.3.4"),
+            ("x-forwarded-for", "198.51.100.9"),
+        ];
+        let ip = client_ip("10.0.0.1:5000", &spoofed);
+        assert_eq!(ip.as_deref(), Some("198.51.100.9"));
+
+        let ip = client_ip("10.0.0.1:5000", &spoofed[..2]);
+        assert_eq!(ip.as_deref(), Some("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_forwarded_header() {
+        let ip = client_ip_from(
+            ProxyHeader::Forwarded,
+            "[::1]:5000",
+            &[
+                ("x-forwarded-for", "1.2.3.4"),
+                (
+                    "forwarded",
+                    r#"for=1.2.3.4, for="[2001:db8::17]:4711";proto=https, For=10.0.0.2"#,
+                ),
+            ],
+        );
+
+        assert_eq!(ip.as_deref(), Some("2001:db8::17"));
+    }
+
+    #[test]
+    fn test_walk_stops_at_unparsable_hops() {
+        let ip = client_ip_from(
+            ProxyHeader::Forwarded,
+            "10.0.0.1:5000",
+            &[("forwarded", "for=1.2.3.4, for=_hidden, for=10.0.0.2")],
+        );
+        assert_eq!(ip.as_deref(), Some("10.0.0.2"));
+
+        // Only trusted hops, the left most one is as far as we can tell
+        let ip = client_ip(
+            "10.0.0.1:5000",
+            &[("x-forwarded-for", "10.0.0.3, 10.0.0.2")],
+        );
+        assert_eq!(ip.as_deref(), Some("10.0.0.3"));
     }
 
-    // fallback
-    "unknown".to_string()
+    #[test]
+    fn test_real_ip_header() {
+        let ip = client_ip_from(
+            ProxyHeader::XRealIp,
+            "10.0.0.1:5000",
+            &[
+                ("x-real-ip", "198.51.100.9"),
+                ("x-forwarded-for", "1.2.3.4"),
+            ],
+        );
+        assert_eq!(ip.as_deref(), Some("198.51.100.9"));
+
+        let ip = client_ip_from(ProxyHeader::XRealIp, "10.0.0.1:5000", &[]);
+        assert_eq!(ip.as_deref(), Some("10.0.0.1"));
+    }
 }
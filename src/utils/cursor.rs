@@ -0,0 +1,60 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+// Keyset position of a todo in the `created_at DESC, id DESC` ordering.
+// Clients only see it as an opaque base64 string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TodoCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl TodoCursor {
+    pub fn new(created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self { created_at, id }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let (micros, id) = raw.split_once(':')?;
+
+        Some(Self {
+            created_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = TodoCursor::new(
+            DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap(),
+            Uuid::new_v4(),
+        );
+
+        assert_eq!(TodoCursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert_eq!(TodoCursor::decode("not a cursor"), None);
+        assert_eq!(
+            TodoCursor::decode(&URL_SAFE_NO_PAD.encode("123:nope")),
+            None
+        );
+    }
+}
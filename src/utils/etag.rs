@@ -0,0 +1,81 @@
+use actix_web::{
+    HttpMessage, HttpRequest,
+    http::header::{self, EntityTag, IfMatch, IfNoneMatch},
+};
+
+// The ETag of a todo is its version, quoted as a strong tag: `"3"`
+pub fn todo_etag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+// Versions accepted by `If-Match`. None means there is no precondition (header missing or `*`),
+// an empty list means none of the given tags can ever match. A header that can't be parsed
+// matches nothing, so the write fails its precondition instead of skipping it.
+pub fn if_match_versions(req: &HttpRequest) -> Option<Vec<i32>> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return None;
+    }
+
+    match req.get_header::<IfMatch>() {
+        None => Some(Vec::new()),
+        Some(IfMatch::Any) => None,
+        Some(IfMatch::Items(tags)) => Some(
+            tags.iter()
+                .filter(|tag| !tag.weak)
+                .filter_map(|tag| tag.tag().parse().ok())
+                .collect(),
+        ),
+    }
+}
+
+// True when `If-None-Match` matches the current version, the client's copy is still fresh
+pub fn is_not_modified(req: &HttpRequest, version: i32) -> bool {
+    let etag = todo_etag(version);
+
+    match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_if_match_versions() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(if_match_versions(&req), None);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "*"))
+            .to_http_request();
+        assert_eq!(if_match_versions(&req), None);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, r#""2", W/"3", "abc""#))
+            .to_http_request();
+        assert_eq!(if_match_versions(&req), Some(vec![2]));
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "2"))
+            .to_http_request();
+        assert_eq!(if_match_versions(&req), Some(vec![]));
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, r#"W/"4""#))
+            .to_http_request();
+
+        assert!(is_not_modified(&req, 4));
+        assert!(!is_not_modified(&req, 5));
+        assert!(!is_not_modified(
+            &TestRequest::default().to_http_request(),
+            4
+        ));
+    }
+}
@@ -2,7 +2,9 @@ use argon2::{
     Argon2, PasswordHash, PasswordVerifier,
     password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
+use sha2::{Digest, Sha256};
 
+#[allow(clippy::let_and_return)]
 pub fn hash_password(password: &str) -> String {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
@@ -27,3 +29,9 @@ pub fn verify_password(password: &str, hash: &str) -> bool {
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok()
 }
+
+// Hex SHA-256 of a one-time token. Tokens are random or signed, so unlike passwords they don't
+// need a slow salted hash, and the digest can be looked up directly.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
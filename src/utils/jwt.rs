@@ -13,6 +13,13 @@ pub struct JwtClaims {
     pub name: String,  // User name
     pub exp: usize,    // Expiration time
     pub iat: usize,    // Issued at
+    pub jti: Uuid,     // Token ID, used to deny-list the token on logout
+    #[serde(default)]
+    pub iat_ms: i64, // Issued at in milliseconds, compared with logout-all and role changes
+    #[serde(default)]
+    pub roles: Vec<String>, // Role names, checked by role_middleware
+    #[serde(default)]
+    pub permissions: Vec<String>, // Permissions of the roles, checked by role_middleware
 }
 
 #[derive(Debug, Clone)]
@@ -40,7 +47,13 @@ impl FromRequest for AuthenticatedUser {
 }
 
 impl JwtClaims {
-    pub fn new(sub: Uuid, email: String, name: String) -> Self {
+    pub fn new(
+        sub: Uuid,
+        email: String,
+        name: String,
+        roles: Vec<String>,
+        permissions: Vec<String>,
+    ) -> Self {
         let now = Utc::now();
         let exp = now + Duration::hours(*config::ACCESS_TOKEN_EXPIRATION_HOURS);
 
@@ -50,6 +63,10 @@ impl JwtClaims {
             name,
             exp: exp.timestamp() as usize,
             iat: now.timestamp() as usize,
+            jti: Uuid::new_v4(),
+            iat_ms: now.timestamp_millis(),
+            roles,
+            permissions,
         }
     }
     pub fn generate_token(&self) -> Result<String, Error> {
@@ -58,6 +75,31 @@ impl JwtClaims {
         encode(&header, self, &encoding_key)
             .map_err(|_| Error::InternalServerError("Failed to generate token".to_string()))
     }
+
+    // Tokens issued before iat_ms was added only know their second, they count from its start
+    pub fn issued_at_ms(&self) -> i64 {
+        if self.iat_ms > 0 {
+            self.iat_ms
+        } else {
+            self.iat as i64 * 1000
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
+
+// Subject of a valid token, for callers where a bad token isn't an error worth logging
+pub fn token_subject(token: &str) -> Option<Uuid> {
+    let decoding_key = DecodingKey::from_secret(config::JWT_SECRET.to_string().as_ref());
+    decode::<JwtClaims>(token, &decoding_key, &Validation::default())
+        .ok()
+        .map(|token_data| token_data.claims.sub)
 }
 
 pub fn verify_token(token: &str) -> Result<JwtClaims, Error> {
@@ -70,3 +112,87 @@ pub fn verify_token(token: &str) -> Result<JwtClaims, Error> {
     })?;
     Ok(token_data.claims)
 }
+
+// What a token mailed to the user may be used for. Each purpose has its own signing key, so a
+// token of one purpose is never accepted for another.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenPurpose {
+    EmailVerification,
+    PasswordReset,
+}
+
+impl TokenPurpose {
+    fn secret(&self) -> String {
+        let purpose = match self {
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::PasswordReset => "password_reset",
+        };
+        format!("{}:{}", *config::JWT_SECRET, purpose)
+    }
+}
+
+// Claims of a single-use action token, its hash is stored in Redis until the token is used
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActionTokenClaims {
+    pub sub: Uuid,     // Subject (user ID)
+    pub email: String, // Email the token was sent to, the token dies if it changes
+    pub purpose: TokenPurpose,
+    pub exp: usize,
+    pub jti: Uuid,
+}
+
+impl ActionTokenClaims {
+    pub fn new(sub: Uuid, email: String, purpose: TokenPurpose, expires_in: Duration) -> Self {
+        ActionTokenClaims {
+            sub,
+            email,
+            purpose,
+            exp: (Utc::now() + expires_in).timestamp() as usize,
+            jti: Uuid::new_v4(),
+        }
+    }
+
+    pub fn generate_token(&self) -> Result<String, Error> {
+        let encoding_key = EncodingKey::from_secret(self.purpose.secret().as_ref());
+        encode(&Header::default(), self, &encoding_key)
+            .map_err(|_| Error::InternalServerError("Failed to generate token".to_string()))
+    }
+}
+
+pub fn verify_action_token(token: &str, purpose: TokenPurpose) -> Result<ActionTokenClaims, Error> {
+    let decoding_key = DecodingKey::from_secret(purpose.secret().as_ref());
+    decode::<ActionTokenClaims>(token, &decoding_key, &Validation::default())
+        .ok()
+        .map(|token_data| token_data.claims)
+        .filter(|claims| claims.purpose == purpose)
+        .ok_or_else(|| Error::BadRequest("Invalid or expired token".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_token_round_trip() {
+        let user_id = Uuid::new_v4();
+        let claims = ActionTokenClaims::new(
+            user_id,
+            "user@example.com".to_string(),
+            TokenPurpose::EmailVerification,
+            Duration::hours(1),
+        );
+        let token = claims.generate_token().unwrap();
+
+        let verified = verify_action_token(&token, TokenPurpose::EmailVerification).unwrap();
+        assert_eq!(verified.sub, user_id);
+        assert_eq!(verified.jti, claims.jti);
+
+        // Access tokens are signed with another key
+        assert!(verify_token(&token).is_err());
+        assert!(verify_action_token(&token, TokenPurpose::PasswordReset).is_err());
+        assert!(
+            verify_action_token(&format!("{}x", token), TokenPurpose::EmailVerification).is_err()
+        );
+    }
+}
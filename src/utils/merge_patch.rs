@@ -0,0 +1,69 @@
+use crate::models::errors::Error;
+use actix_web::{HttpRequest, http::header::CONTENT_TYPE};
+use serde::{Deserialize, Deserializer};
+
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+// RFC 7396 fields are tri-state, use with `#[serde(default, deserialize_with = ...)]`:
+// absent -> None (keep), null -> Some(None) (clear), value -> Some(Some(value)) (replace)
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+pub fn require_merge_patch(req: &HttpRequest) -> Result<(), Error> {
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim);
+
+    match content_type {
+        Some(content_type) if content_type.eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE) => Ok(()),
+        _ => Err(Error::UnsupportedMediaType(format!(
+            "Content-Type must be {}",
+            MERGE_PATCH_CONTENT_TYPE
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[derive(Deserialize)]
+    struct Patch {
+        #[serde(default, deserialize_with = "deserialize_nullable")]
+        title: Option<Option<String>>,
+    }
+
+    #[test]
+    fn test_deserialize_nullable_distinguishes_absent_and_null() {
+        let patch: Patch = serde_json::from_str(r#"{}"#).unwrap();
+        assert_eq!(patch.title, None);
+
+        let patch: Patch = serde_json::from_str(r#"{"title": null}"#).unwrap();
+        assert_eq!(patch.title, Some(None));
+
+        let patch: Patch = serde_json::from_str(r#"{"title": "Buy milk"}"#).unwrap();
+        assert_eq!(patch.title, Some(Some("Buy milk".to_string())));
+    }
+
+    #[test]
+    fn test_require_merge_patch() {
+        let req = TestRequest::default()
+            .insert_header((CONTENT_TYPE, "application/merge-patch+json; charset=utf-8"))
+            .to_http_request();
+        assert!(require_merge_patch(&req).is_ok());
+
+        let req = TestRequest::default()
+            .insert_header((CONTENT_TYPE, "application/json"))
+            .to_http_request();
+        assert!(require_merge_patch(&req).is_err());
+    }
+}
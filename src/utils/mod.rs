@@ -1,5 +1,10 @@
 pub mod common;
+pub mod cursor;
+pub mod etag;
 pub mod hash;
 pub mod jwt;
+pub mod merge_patch;
+pub mod recurrence;
 pub mod request_handler;
 pub mod response_handler;
+pub mod sse;
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Datelike, Duration, Months, Utc, Weekday};
+use croner::Cron;
+
+// Recurrence rule of a todo, either a cron expression (`0 0 9 * * MON`, seconds optional, UTC)
+// or a subset of RFC 5545 RRULE (`RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20261231T000000Z`)
+#[derive(Debug, Clone)]
+pub enum Recurrence {
+    Cron(Cron),
+    Rule(RRule),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    // Only supported with FREQ=WEEKLY, sorted from Monday
+    pub by_day: Vec<Weekday>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Recurrence {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let upper = rule.to_ascii_uppercase();
+
+        if let Some(rrule) = upper.strip_prefix("RRULE:") {
+            return RRule::parse(rrule).map(Recurrence::Rule);
+        }
+
+        if upper.starts_with("FREQ=") {
+            return RRule::parse(&upper).map(Recurrence::Rule);
+        }
+
+        Cron::new(rule)
+            .with_seconds_optional()
+            .parse()
+            .map(Recurrence::Cron)
+            .map_err(|e| format!("Invalid cron expression: {}", e))
+    }
+
+    // Next occurrence strictly after `previous`, None once the rule has ended. `series_start`
+    // is the first occurrence of the series.
+    pub fn next_after(
+        &self,
+        previous: DateTime<Utc>,
+        series_start: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Recurrence::Cron(cron) => cron.find_next_occurrence(&previous, false).ok(),
+            Recurrence::Rule(rule) => rule.next_after(previous, series_start),
+        }
+    }
+}
+
+impl RRule {
+    fn parse(rule: &str) -> Result<Self, String> {
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut until = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part: {}", part))?;
+
+            match name {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported FREQ: {}", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid INTERVAL: {}", value))?
+                }
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(Self::parse_weekday)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                "UNTIL" => {
+                    until = Some(
+                        DateTime::parse_from_str(&format!("{}+0000", value), "%Y%m%dT%H%M%SZ%z")
+                            .map_err(|_| format!("Invalid UNTIL: {}", value))?
+                            .with_timezone(&Utc),
+                    )
+                }
+                _ => return Err(format!("Unsupported RRULE part: {}", name)),
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| "RRULE must have a FREQ".to_string())?;
+
+        if !by_day.is_empty() && frequency != Frequency::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+
+        by_day.sort_by_key(Weekday::num_days_from_monday);
+        by_day.dedup();
+
+        Ok(Self {
+            frequency,
+            interval,
+            by_day,
+            until,
+        })
+    }
+
+    fn parse_weekday(day: &str) -> Result<Weekday, String> {
+        match day {
+            "MO" => Ok(Weekday::Mon),
+            "TU" => Ok(Weekday::Tue),
+            "WE" => Ok(Weekday::Wed),
+            "TH" => Ok(Weekday::Thu),
+            "FR" => Ok(Weekday::Fri),
+            "SA" => Ok(Weekday::Sat),
+            "SU" => Ok(Weekday::Sun),
+            _ => Err(format!("Invalid BYDAY: {}", day)),
+        }
+    }
+
+    // The time of day of `previous` is kept for every occurrence
+    fn next_after(
+        &self,
+        previous: DateTime<Utc>,
+        series_start: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let next = match self.frequency {
+            Frequency::Daily => previous + Duration::days(self.interval.into()),
+            Frequency::Weekly if self.by_day.is_empty() => {
+                previous + Duration::weeks(self.interval.into())
+            }
+            Frequency::Weekly => {
+                let weekday = previous.weekday().num_days_from_monday();
+
+                // A later day in the same week, otherwise the first day `interval` weeks later
+                match self
+                    .by_day
+                    .iter()
+                    .map(Weekday::num_days_from_monday)
+                    .find(|day| *day > weekday)
+                {
+                    Some(day) => previous + Duration::days((day - weekday).into()),
+                    None => {
+                        let week_start = previous - Duration::days(weekday.into());
+                        let first_day = self.by_day[0].num_days_from_monday();
+                        week_start
+                            + Duration::weeks(self.interval.into())
+                            + Duration::days(first_day.into())
+                    }
+                }
+            }
+            Frequency::Monthly => Self::nth_month_after(previous, series_start, self.interval)?,
+            Frequency::Yearly => Self::nth_month_after(previous, series_start, self.interval * 12)?,
+        };
+
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    // First `series_start + n * step` months after `previous`. Counted from the start rather than
+    // from `previous`, which may have been moved back to the end of a shorter month.
+    fn nth_month_after(
+        previous: DateTime<Utc>,
+        series_start: DateTime<Utc>,
+        step: u32,
+    ) -> Option<DateTime<Utc>> {
+        let elapsed = (previous.year() - series_start.year()) * 12 + previous.month() as i32
+            - series_start.month() as i32;
+        let mut n = (elapsed.max(0) as u32) / step;
+
+        loop {
+            let next = series_start.checked_add_months(Months::new(n.checked_mul(step)?))?;
+            if next > previous {
+                return Some(next);
+            }
+            n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_weekly_rrule_with_days_and_interval() {
+        let rule = Recurrence::parse("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,MO").unwrap();
+
+        // Monday 2026-10-12 09:00 -> Thursday of the same week -> Monday two weeks later
+        let monday = Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap();
+        let thursday = rule.next_after(monday, monday).unwrap();
+        assert_eq!(
+            thursday,
+            Utc.with_ymd_and_hms(2026, 10, 15, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            rule.next_after(thursday, monday),
+            Some(Utc.with_ymd_and_hms(2026, 10, 26, 9, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_rrule_stops_after_until() {
+        let rule = Recurrence::parse("FREQ=DAILY;UNTIL=20261013T000000Z").unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap();
+
+        assert_eq!(rule.next_after(start, start), None);
+    }
+
+    #[test]
+    fn test_cron_recurrence() {
+        let rule = Recurrence::parse("0 0 9 * * MON").unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap();
+
+        assert_eq!(
+            rule.next_after(start, start),
+            Some(Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_monthly_series_keeps_the_day_of_its_start() {
+        let rule = Recurrence::parse("RRULE:FREQ=MONTHLY").unwrap();
+        let start = Utc.with_ymd_and_hms(2027, 1, 31, 9, 0, 0).unwrap();
+
+        let mut due_at = start;
+        let mut days = Vec::new();
+        for _ in 0..4 {
+            due_at = rule.next_after(due_at, start).unwrap();
+            days.push((due_at.month(), due_at.day()));
+        }
+        assert_eq!(days, vec![(2, 28), (3, 31), (4, 30), (5, 31)]);
+
+        // Feb 29 only comes back in leap years
+        let rule = Recurrence::parse("RRULE:FREQ=YEARLY").unwrap();
+        let start = Utc.with_ymd_and_hms(2028, 2, 29, 9, 0, 0).unwrap();
+        let next = rule.next_after(start, start).unwrap();
+        assert_eq!((next.year(), next.month(), next.day()), (2029, 2, 28));
+        let leap = rule
+            .next_after(Utc.with_ymd_and_hms(2031, 2, 28, 9, 0, 0).unwrap(), start)
+            .unwrap();
+        assert_eq!((leap.year(), leap.month(), leap.day()), (2032, 2, 29));
+    }
+
+    #[test]
+    fn test_rejects_unsupported_rules() {
+        assert!(Recurrence::parse("RRULE:FREQ=HOURLY").is_err());
+        assert!(Recurrence::parse("RRULE:FREQ=MONTHLY;BYDAY=MO").is_err());
+        assert!(Recurrence::parse("RRULE:INTERVAL=2").is_err());
+        assert!(Recurrence::parse("every monday").is_err());
+    }
+}
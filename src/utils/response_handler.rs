@@ -3,6 +3,7 @@ use derive_more::Display;
 use serde::Serialize;
 use serde_json::json;
 
+#[allow(clippy::crate_in_macro_def)]
 #[macro_export]
 macro_rules! handle_response {
     // Truyền mỗi Result<T, Error> vào macro này
@@ -53,6 +54,7 @@ impl ResponseError for ValidateErrorResponse {
     }
 }
 
+#[allow(clippy::unnecessary_cast)]
 pub fn validator_error_handler(e: ::validator::ValidationErrors, _: &HttpRequest) -> Error {
     let mut errors = Vec::new();
     for (_, field_errors) in e.field_errors() {
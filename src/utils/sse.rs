@@ -0,0 +1,175 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use actix_web::web::Bytes;
+use futures::{Stream, stream};
+use tokio::{
+    sync::broadcast::{Receiver, error::RecvError},
+    time::{Instant, Interval, interval_at, sleep_until},
+};
+use uuid::Uuid;
+
+use crate::{config, services::event_service::TodoEventMessage};
+
+const KEEP_ALIVE: &[u8] = b": keep-alive\n\n";
+// Sent when the stream fell behind and dropped events, the client should refetch its todos
+const RESYNC: &[u8] = b"event: resync\ndata: {}\n\n";
+
+struct EventStream<F> {
+    receiver: Receiver<Arc<TodoEventMessage>>,
+    user_id: Uuid,
+    keep_alive: Interval,
+    session_check: Interval,
+    expires_at: Instant,
+    is_session_active: F,
+}
+
+// Server-Sent Events of the todos `user_id` can see. A comment line is sent while idle so
+// proxies don't close the connection. The stream ends when the access token expires, or when
+// `is_session_active` finds it revoked on one of its periodic checks; the client reconnects
+// with a fresh token.
+pub fn todo_event_stream<F, Fut>(
+    receiver: Receiver<Arc<TodoEventMessage>>,
+    user_id: Uuid,
+    expires_in: Duration,
+    is_session_active: F,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> + 'static
+where
+    F: Fn() -> Fut + 'static,
+    Fut: Future<Output = bool> + 'static,
+{
+    let period = Duration::from_secs(*config::TODO_EVENTS_KEEP_ALIVE_SECS);
+    let check_period = Duration::from_secs(*config::TODO_EVENTS_SESSION_CHECK_SECS);
+    let now = Instant::now();
+
+    let state = EventStream {
+        receiver,
+        user_id,
+        keep_alive: interval_at(now + period, period),
+        session_check: interval_at(now + check_period, check_period),
+        expires_at: now + expires_in,
+        is_session_active,
+    };
+
+    stream::unfold(state, next_frame)
+}
+
+async fn next_frame<F, Fut>(
+    mut state: EventStream<F>,
+) -> Option<(Result<Bytes, actix_web::Error>, EventStream<F>)>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    loop {
+        let frame = tokio::select! {
+            biased;
+
+            _ = sleep_until(state.expires_at) => return None,
+            _ = state.session_check.tick() => {
+                if !(state.is_session_active)().await {
+                    return None;
+                }
+                continue;
+            }
+            _ = state.keep_alive.tick() => Bytes::from_static(KEEP_ALIVE),
+            message = state.receiver.recv() => match message {
+                Ok(message) if message.user_ids.contains(&state.user_id) => {
+                    match serde_json::to_string(&message.event) {
+                        Ok(data) => Bytes::from(format!("data: {}\n\n", data)),
+                        Err(e) => {
+                            log::error!("failed to serialize todo event: {:?}", e);
+                            continue;
+                        }
+                    }
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => Bytes::from_static(RESYNC),
+                Err(RecvError::Closed) => return None,
+            },
+        };
+
+        state.keep_alive.reset();
+        return Some((Ok(frame), state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_service::{TodoEvent, TodoEventType};
+    use chrono::Utc;
+    use entity::{sea_orm_active_enums::TodoPriority, t_todos};
+    use futures::StreamExt;
+    use tokio::sync::broadcast;
+
+    fn message(user_ids: Vec<Uuid>, title: &str) -> Arc<TodoEventMessage> {
+        let now = Utc::now();
+        Arc::new(TodoEventMessage {
+            user_ids,
+            event: TodoEvent {
+                event_type: TodoEventType::Created,
+                todo: t_todos::Model {
+                    id: Uuid::new_v4(),
+                    title: title.to_string(),
+                    description: String::new(),
+                    completed: false,
+                    created_at: now,
+                    updated_at: now,
+                    user_id: Uuid::new_v4(),
+                    deleted_at: None,
+                    version: 1,
+                    due_at: None,
+                    priority: TodoPriority::Medium,
+                    remind_at: None,
+                    reminder_sent_at: None,
+                    recurrence: None,
+                    series_id: None,
+                    series_start_at: None,
+                    list_id: None,
+                },
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stream_only_sends_events_of_the_user() {
+        let user_id = Uuid::new_v4();
+        let (sender, receiver) = broadcast::channel(8);
+        let mut stream = Box::pin(todo_event_stream(
+            receiver,
+            user_id,
+            Duration::from_secs(60),
+            || async { true },
+        ));
+
+        sender
+            .send(message(vec![Uuid::new_v4()], "Someone else's"))
+            .unwrap();
+        sender
+            .send(message(vec![Uuid::new_v4(), user_id], "Shared"))
+            .unwrap();
+        drop(sender);
+
+        let frame = stream.next().await.unwrap().unwrap();
+        let frame = std::str::from_utf8(&frame).unwrap();
+        assert!(frame.starts_with("data: {\"type\":\"created\""));
+        assert!(frame.contains("\"title\":\"Shared\""));
+        assert!(frame.ends_with("\n\n"));
+
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_when_the_token_expires() {
+        let (sender, receiver) = broadcast::channel(8);
+        let mut stream = Box::pin(todo_event_stream(
+            receiver,
+            Uuid::new_v4(),
+            Duration::ZERO,
+            || async { true },
+        ));
+
+        assert!(stream.next().await.is_none());
+        drop(sender);
+    }
+}
@@ -1,9 +1,20 @@
+use crate::{
+    models::request::{
+        CreateTodoRequest, GetAllTodosRequest, PatchTodoRequest, PatchUserRequest, TodoSort,
+        UpdateTodoRequest,
+    },
+    utils::recurrence::Recurrence,
+};
+use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use std::borrow::Cow;
 use validator::ValidationError;
 
 static RE_SPECIAL_CHAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"^.*?[@$!%*?&].*$").unwrap());
+static RE_HEX_COLOR: Lazy<Regex> = Lazy::new(|| Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap());
 
+#[allow(clippy::is_digit_ascii_radix)]
 pub fn validate_password(password: &str) -> Result<(), ValidationError> {
     let mut has_whitespace = false;
     let mut has_upper = false;
@@ -30,3 +41,122 @@ pub fn validate_password(password: &str) -> Result<(), ValidationError> {
         ))
     }
 }
+
+pub fn validate_todo_sort(sort: &str) -> Result<(), ValidationError> {
+    match TodoSort::parse(sort) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("invalid_sort").with_message(Cow::from(
+            "Sort must be one of createdAt, updatedAt, title, completed, dueAt, priority, optionally followed by :asc or :desc",
+        ))),
+    }
+}
+
+pub fn validate_todo_filters(params: &GetAllTodosRequest) -> Result<(), ValidationError> {
+    let is_valid_range = |from, to| match (from, to) {
+        (Some(from), Some(to)) => from <= to,
+        _ => true,
+    };
+
+    if !is_valid_range(params.created_from, params.created_to) {
+        return Err(ValidationError::new("invalid_range")
+            .with_message(Cow::from("createdFrom must be before createdTo")));
+    }
+
+    if !is_valid_range(params.updated_from, params.updated_to) {
+        return Err(ValidationError::new("invalid_range")
+            .with_message(Cow::from("updatedFrom must be before updatedTo")));
+    }
+
+    if !is_valid_range(params.due_from, params.due_to) {
+        return Err(ValidationError::new("invalid_range")
+            .with_message(Cow::from("dueFrom must be before dueTo")));
+    }
+
+    Ok(())
+}
+
+// `null` (clear) is rejected for the columns that are not nullable instead of being ignored
+fn reject_null(field: &'static str, is_null: bool) -> Result<(), ValidationError> {
+    if is_null {
+        return Err(ValidationError::new("null_value")
+            .with_message(Cow::from(format!("{} cannot be null", field))));
+    }
+
+    Ok(())
+}
+
+pub fn validate_recurrence(recurrence: &str) -> Result<(), ValidationError> {
+    Recurrence::parse(recurrence)
+        .map(|_| ())
+        .map_err(|e| ValidationError::new("invalid_recurrence").with_message(Cow::from(e)))
+}
+
+// Shared with TodoRepository, which checks the schedule a patch results in
+pub fn check_schedule(
+    due_at: Option<DateTime<Utc>>,
+    remind_at: Option<DateTime<Utc>>,
+    has_recurrence: bool,
+) -> Result<(), &'static str> {
+    // A reminder after the due date would fire too late to be useful
+    if let (Some(due_at), Some(remind_at)) = (due_at, remind_at)
+        && remind_at > due_at
+    {
+        return Err("remindAt must not be after dueAt");
+    }
+
+    // Occurrences are scheduled from the due date of the previous one
+    if has_recurrence && due_at.is_none() {
+        return Err("recurrence requires dueAt");
+    }
+
+    Ok(())
+}
+
+fn validate_schedule(
+    due_at: Option<DateTime<Utc>>,
+    remind_at: Option<DateTime<Utc>>,
+    has_recurrence: bool,
+) -> Result<(), ValidationError> {
+    check_schedule(due_at, remind_at, has_recurrence).map_err(|message| {
+        ValidationError::new("invalid_schedule").with_message(Cow::from(message))
+    })
+}
+
+pub fn validate_create_todo(body: &CreateTodoRequest) -> Result<(), ValidationError> {
+    validate_schedule(body.due_at, body.remind_at, body.recurrence.is_some())
+}
+
+pub fn validate_update_todo(body: &UpdateTodoRequest) -> Result<(), ValidationError> {
+    validate_schedule(body.due_at, body.remind_at, body.recurrence.is_some())
+}
+
+pub fn validate_todo_patch(patch: &PatchTodoRequest) -> Result<(), ValidationError> {
+    reject_null("title", matches!(patch.title, Some(None)))?;
+    reject_null("description", matches!(patch.description, Some(None)))?;
+    reject_null("completed", matches!(patch.completed, Some(None)))?;
+    // dueAt/remindAt/recurrence are checked against the stored todo by TodoRepository::update
+    reject_null("priority", matches!(patch.priority, Some(None)))
+}
+
+pub fn validate_user_patch(patch: &PatchUserRequest) -> Result<(), ValidationError> {
+    reject_null("name", matches!(patch.name, Some(None)))?;
+    reject_null("password", matches!(patch.password, Some(None)))
+}
+
+// Names are saved trimmed, so only whitespace would store an empty string
+pub fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message(Cow::from("Must not be blank")));
+    }
+
+    Ok(())
+}
+
+pub fn validate_tag_color(color: &str) -> Result<(), ValidationError> {
+    if RE_HEX_COLOR.is_match(color) {
+        return Ok(());
+    }
+
+    Err(ValidationError::new("invalid_color")
+        .with_message(Cow::from("Color must be a hex color like #1e90ff")))
+}